@@ -1,22 +1,42 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
-use fantoccini::{ClientBuilder, Locator};
-use sshn_lib::{generate_auth_url, get_code_challenge, AuthenticatedClient, LoginType};
+use fantoccini::{wd::Capabilities, ClientBuilder, Locator};
+use serde_json::json;
+use sshn_lib::{
+    generate_auth_url, get_code_challenge, AuthenticatedClient, LoginType, REDIRECT_URI,
+};
+use url::Url;
 
 use crate::{
+    config,
     error::{Error, Result},
     secrets::{self, Credentials},
+    webdriver::WebDriverProcess,
     WebDriver,
 };
 
 const LOGIN_FORM_ID: &str = "kc-form-login";
 
+/// The messages shown by the login portal when it rejects the submitted form, e.g. for a wrong password.
+const LOGIN_ERROR_SELECTOR: &str = "#input-error, #kc-error-message, .alert-error";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 #[derive(Debug)]
 pub struct AuthOptions {
     webdriver: WebDriver,
     webdriver_port: u16,
     auto_start_webdriver: bool,
     login_base_url: Option<String>,
+    remember_password: bool,
+    webdriver_url: Option<String>,
+    webdriver_path: Option<PathBuf>,
+    headless: bool,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    webdriver_timeout: Duration,
+    login_timeout: Duration,
+    debug_dir: Option<PathBuf>,
 }
 
 impl AuthOptions {
@@ -44,6 +64,133 @@ impl AuthOptions {
             ..self
         }
     }
+
+    /// Whether to store the username and password, so a new login can be done once the session expires.
+    pub fn remember_password(self, remember_password: bool) -> Self {
+        Self {
+            remember_password,
+            ..self
+        }
+    }
+
+    /// Connect to an already running web driver at the given url, e.g. a remote Selenium grid.
+    pub fn webdriver_url<U: Into<String>>(self, webdriver_url: U) -> Self {
+        Self {
+            webdriver_url: Some(webdriver_url.into()),
+            ..self
+        }
+    }
+
+    /// The web driver binary to start, instead of looking it up in `PATH`.
+    pub fn webdriver_path<P: Into<PathBuf>>(self, webdriver_path: P) -> Self {
+        Self {
+            webdriver_path: Some(webdriver_path.into()),
+            ..self
+        }
+    }
+
+    pub fn headless(self, headless: bool) -> Self {
+        Self { headless, ..self }
+    }
+
+    pub fn user_agent<U: Into<String>>(self, user_agent: U) -> Self {
+        Self {
+            user_agent: Some(user_agent.into()),
+            ..self
+        }
+    }
+
+    /// The proxy the browser should use, in the form `host:port`.
+    pub fn proxy<P: Into<String>>(self, proxy: P) -> Self {
+        Self {
+            proxy: Some(proxy.into()),
+            ..self
+        }
+    }
+
+    /// How long to wait for a started web driver to accept connections.
+    pub fn webdriver_timeout(self, webdriver_timeout: Duration) -> Self {
+        Self {
+            webdriver_timeout,
+            ..self
+        }
+    }
+
+    /// How long to wait for the login portal to redirect back to SSHN after submitting the login form.
+    pub fn login_timeout(self, login_timeout: Duration) -> Self {
+        Self {
+            login_timeout,
+            ..self
+        }
+    }
+
+    /// The directory where a screenshot and the page source are saved when the login fails.
+    pub fn debug_dir<D: Into<PathBuf>>(self, debug_dir: D) -> Self {
+        Self {
+            debug_dir: Some(debug_dir.into()),
+            ..self
+        }
+    }
+
+    /// The url of the web driver to connect to.
+    fn connect_url(&self) -> String {
+        self.webdriver_url
+            .clone()
+            .unwrap_or_else(|| format!("http://localhost:{}", self.webdriver_port))
+    }
+
+    /// The capabilities requested from the browser when creating a new session.
+    /// The browser is named explicitly, so a remote grid with multiple browsers starts the one the options are meant for.
+    fn capabilities(&self) -> Capabilities {
+        let mut capabilities = Capabilities::new();
+
+        match self.webdriver {
+            WebDriver::Chromium => {
+                let mut args = Vec::new();
+
+                if self.headless {
+                    args.push(String::from("--headless=new"));
+                }
+
+                if let Some(user_agent) = self.user_agent.as_ref() {
+                    args.push(format!("--user-agent={}", user_agent));
+                }
+
+                capabilities.insert(String::from("browserName"), json!("chrome"));
+                capabilities.insert(String::from("goog:chromeOptions"), json!({ "args": args }));
+            }
+            WebDriver::Gecko => {
+                let mut args = Vec::new();
+                let mut prefs = serde_json::Map::new();
+
+                if self.headless {
+                    args.push(String::from("-headless"));
+                }
+
+                if let Some(user_agent) = self.user_agent.as_ref() {
+                    prefs.insert(
+                        String::from("general.useragent.override"),
+                        json!(user_agent),
+                    );
+                }
+
+                capabilities.insert(String::from("browserName"), json!("firefox"));
+                capabilities.insert(
+                    String::from("moz:firefoxOptions"),
+                    json!({ "args": args, "prefs": prefs }),
+                );
+            }
+        }
+
+        if let Some(proxy) = self.proxy.as_ref() {
+            capabilities.insert(
+                String::from("proxy"),
+                json!({ "proxyType": "manual", "httpProxy": proxy, "sslProxy": proxy }),
+            );
+        }
+
+        capabilities
+    }
 }
 
 impl Default for AuthOptions {
@@ -53,48 +200,105 @@ impl Default for AuthOptions {
             webdriver: WebDriver::Chromium,
             auto_start_webdriver: true,
             webdriver_port: 4444,
+            remember_password: false,
+            webdriver_url: None,
+            webdriver_path: None,
+            headless: true,
+            user_agent: None,
+            proxy: None,
+            webdriver_timeout: Duration::from_secs(10),
+            login_timeout: Duration::from_secs(30),
+            debug_dir: None,
         }
     }
 }
 
-/// Starts the given webdriver on the given port, then waits until said driver has started up.
-async fn start_web_driver(webdriver: WebDriver, port: u16) -> Result<tokio::process::Child> {
-    let process = match webdriver {
-        WebDriver::Chromium => tokio::process::Command::new("chromedriver")
-            .arg(format!("--port={}", port))
-            .arg("--headless=new")
-            .spawn()?,
-        WebDriver::Gecko => tokio::process::Command::new("geckodriver")
-            .arg("--port")
-            .arg(port.to_string())
-            .spawn()?,
-    };
+/// Fill in the login form, then wait until the login portal redirects back to SSHN.
+async fn submit_login_form(
+    browser: &fantoccini::Client,
+    login_url: &str,
+    username: &str,
+    password: &str,
+    options: &AuthOptions,
+) -> Result<Url> {
+    log::info!("Logging into SSHN at {}", login_url);
+
+    browser.goto(login_url).await?;
+
+    let login_form = browser.form(Locator::Id(LOGIN_FORM_ID)).await?;
+
+    login_form
+        .set_by_name("username", username)
+        .await?
+        .set_by_name("password", password)
+        .await?
+        .submit_direct()
+        .await?;
 
-    let mut attempt = 0;
-    let max_attempts = 10;
-
-    while attempt < max_attempts {
-        if tokio::net::TcpStream::connect(("127.0.0.1", port))
-            .await
-            .is_ok()
-        {
-            break;
-        } else {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            attempt += 1;
-
-            log::warn!(
-                "Could not connect to web driver, retrying... (Attempt {})",
-                attempt
-            );
+    let deadline = tokio::time::Instant::now() + options.login_timeout;
+
+    loop {
+        let current_url = browser.current_url().await?;
+
+        if current_url.as_str().starts_with(REDIRECT_URI) {
+            return Ok(current_url);
+        }
+
+        if let Some(message) = login_error(browser).await {
+            return Err(Error::LoginRejected(message));
         }
+
+        if tokio::time::Instant::now() >= deadline {
+            log::debug!("Login portal did not redirect, ended up at {}", current_url);
+
+            let debug_dir = save_debug_info(browser, options).await;
+
+            return Err(Error::CallbackTimeout(debug_dir));
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
     }
+}
+
+/// The error message shown by the login portal, if it rejected the login.
+async fn login_error(browser: &fantoccini::Client) -> Option<String> {
+    let element = browser
+        .find(Locator::Css(LOGIN_ERROR_SELECTOR))
+        .await
+        .ok()?;
+
+    let message = element.text().await.ok()?;
 
-    if attempt >= max_attempts {
-        return Err(Error::WebDriverStart);
+    Some(message.trim().to_string())
+}
+
+/// Save a screenshot and the source of the current page, if a debug directory is set,
+/// returning the directory they were saved to.
+async fn save_debug_info(browser: &fantoccini::Client, options: &AuthOptions) -> Option<PathBuf> {
+    // The page might contain personal details, so it is only saved when asked for.
+    let debug_dir = options.debug_dir.clone()?;
+
+    let result: Result<()> = async {
+        tokio::fs::create_dir_all(&debug_dir).await?;
+
+        let screenshot = browser.screenshot().await?;
+        tokio::fs::write(debug_dir.join("screenshot.png"), screenshot).await?;
+
+        let source = browser.source().await?;
+        tokio::fs::write(debug_dir.join("page.html"), source).await?;
+
+        Ok(())
     }
+    .await;
 
-    Ok(process)
+    match result {
+        Ok(()) => Some(debug_dir),
+        Err(error) => {
+            log::warn!("Failed to save debug information: {}", error);
+
+            None
+        }
+    }
 }
 
 pub async fn headless_login<U: AsRef<str>, P: AsRef<str>>(
@@ -102,7 +306,7 @@ pub async fn headless_login<U: AsRef<str>, P: AsRef<str>>(
     password: P,
     options: AuthOptions,
 ) -> Result<AuthenticatedClient> {
-    let client = sshn_lib::UnAuthenticatedClient::new(None);
+    let client = config::client();
 
     let (code_challenge, code_verifier) = get_code_challenge();
 
@@ -121,42 +325,48 @@ pub async fn headless_login<U: AsRef<str>, P: AsRef<str>>(
 
     let login_url = generate_auth_url(login_base_url, code_challenge)?;
 
-    let mut driver = if options.auto_start_webdriver {
-        let driver = start_web_driver(options.webdriver, options.webdriver_port).await?;
+    // A web driver running at a given url is never started by us.
+    let driver = if options.auto_start_webdriver && options.webdriver_url.is_none() {
+        let driver = WebDriverProcess::start(
+            options.webdriver.clone(),
+            options.webdriver_path.clone(),
+            options.webdriver_port,
+            options.webdriver_timeout,
+        )
+        .await?;
 
         Some(driver)
     } else {
         None
     };
 
-    let callback_url = {
-        let browser = ClientBuilder::native()
-            .connect(&format!("http://localhost:{}", options.webdriver_port))
-            .await?;
-
-        log::info!("Logging into SSHN at {}", login_url);
-
-        browser.goto(&login_url).await?;
-
-        let login_form = browser.form(Locator::Id(LOGIN_FORM_ID)).await?;
+    let webdriver_url = match driver.as_ref() {
+        Some(driver) => driver.url(),
+        None => options.connect_url(),
+    };
 
-        login_form
-            .set_by_name("username", username.as_ref())
-            .await?
-            .set_by_name("password", password.as_ref())
-            .await?
-            .submit_direct()
-            .await?;
+    let browser = ClientBuilder::native()
+        .capabilities(options.capabilities())
+        .connect(&webdriver_url)
+        .await?;
 
-        let callback_url = browser.current_url().await?;
+    let result = submit_login_form(
+        &browser,
+        &login_url,
+        username.as_ref(),
+        password.as_ref(),
+        &options,
+    )
+    .await;
+
+    if let Err(error) = browser.close().await {
+        log::warn!("Failed to close browser: {}", error);
+    }
 
-        browser.close_window().await?;
+    let callback_url = result?;
 
-        callback_url
-    };
-
-    if let Some(driver) = driver.as_mut() {
-        driver.kill().await?;
+    if let Some(driver) = driver {
+        driver.stop().await?;
     }
 
     let authorization_code = callback_url
@@ -171,12 +381,18 @@ pub async fn headless_login<U: AsRef<str>, P: AsRef<str>>(
             code: authorization_code,
             verifier: code_verifier,
         })
-        .await?;
+        .await?
+        .on_tokens_changed(secrets::store_tokens);
+
+    if options.remember_password {
+        let credentials = Credentials::new(username.as_ref(), password.as_ref());
 
-    let credentials = Credentials::new(username.as_ref(), password.as_ref());
+        secrets::set("credentials", &credentials)?;
+    } else {
+        secrets::delete("credentials")?;
+    }
 
-    secrets::set("credentials", &credentials)?;
-    secrets::set("tokens", auth_client.tokens())?;
+    secrets::set("tokens", &auth_client.tokens())?;
 
     Ok(auth_client)
 }
@@ -1,71 +1,277 @@
-use sshn_lib::Client;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sshn_lib::{publication::Publication, Client, Tokens};
 
 use crate::{
-    auth::{self, AuthOptions},
-    error::Result,
+    auth,
+    config::{self, Filters},
+    error::{Error, Result},
+    notify::{Event, Notifiers},
+    profiles::{self, Profiles, Settings},
     secrets,
 };
 
+mod watch;
+
+pub use watch::watch;
+
+#[derive(Serialize, Debug)]
+pub struct LoginResult {
+    pub profile: String,
+    pub username: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Log in and remember the given settings for the current profile.
 pub async fn login<U: AsRef<str>, P: AsRef<str>>(
     username: U,
     password: P,
-    options: AuthOptions,
-) -> Result<()> {
-    auth::headless_login(username.as_ref(), password.as_ref(), options).await?;
+    settings: Settings,
+    remember_password: bool,
+) -> Result<LoginResult> {
+    // Only the given settings are saved, the configuration file is merged in whenever they are used.
+    let merged = settings.clone().or(config::get().auth.clone());
+
+    // The web driver is only started when asked for, unlike when logging in again automatically,
+    // so the default is not saved with the settings.
+    let options = merged
+        .auth_options()
+        .auto_start_webdriver(merged.auto_start_webdriver.unwrap_or(false))
+        .remember_password(remember_password);
+
+    let client = auth::headless_login(username.as_ref(), password.as_ref(), options).await?;
+
+    let mut profiles = Profiles::load()?;
+
+    profiles
+        .get_or_add(profiles::current())?
+        .set_settings(settings);
+
+    profiles.save()?;
 
-    Ok(())
+    let claims = client.tokens().claims().ok();
+
+    Ok(LoginResult {
+        profile: profiles::current().to_string(),
+        username: username.as_ref().to_string(),
+        name: claims
+            .as_ref()
+            .and_then(|claims| claims.name().map(String::from)),
+        email: claims.and_then(|claims| claims.email().map(String::from)),
+    })
 }
 
-pub async fn list(limit: usize) -> Result<prettytable::Table> {
-    use prettytable::{Cell, Row, Table};
+#[derive(Serialize, Debug)]
+pub struct LogoutResult {
+    pub profile: String,
+    pub forgot_credentials: bool,
+}
 
-    let limit = limit as i64;
+pub async fn logout(forget_credentials: bool) -> Result<LogoutResult> {
+    if let Ok(tokens) = secrets::get::<_, Tokens>("tokens") {
+        if !tokens.refresh_token().has_expired() {
+            let client = config::client();
 
-    let missing_credentials = secrets::get::<_, secrets::Credentials>("credentials").is_err();
+            if let Err(error) = client.logout(tokens.refresh_token().content()).await {
+                log::warn!("Failed to end session at SSHN: {}", error);
+            }
+        }
+    }
 
-    let publications = if missing_credentials {
-        let mut client = sshn_lib::UnAuthenticatedClient::new(None);
+    secrets::delete("tokens")?;
 
-        client.get_publications_list(limit).await?
-    } else {
-        let mut client = secrets::get_client().await?;
+    if forget_credentials {
+        secrets::delete("credentials")?;
+    }
+
+    Ok(LogoutResult {
+        profile: profiles::current().to_string(),
+        forgot_credentials: forget_credentials,
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct Status {
+    pub profile: String,
+    pub logged_in: bool,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub access_expires: Option<DateTime<Utc>>,
+    pub session_expires: Option<DateTime<Utc>>,
+    pub session_expired: bool,
+    pub has_credentials: bool,
+}
 
-        client.get_publications_list(limit).await?
+/// Get the state of the stored session, without logging in again.
+pub async fn status() -> Result<Status> {
+    let tokens = secrets::get::<_, Tokens>("tokens").ok();
+
+    let has_credentials = secrets::get::<_, secrets::Credentials>("credentials").is_ok();
+
+    let mut status = Status {
+        profile: profiles::current().to_string(),
+        logged_in: tokens.is_some(),
+        name: None,
+        email: None,
+        access_expires: None,
+        session_expires: None,
+        session_expired: false,
+        has_credentials,
     };
 
-    let mut table = Table::new();
-
-    table.add_row(Row::new(vec![
-        Cell::new("Can reply?"),
-        Cell::new("Name"),
-        Cell::new("City"),
-        Cell::new("Number of applicants"),
-        Cell::new("Gross rent"),
-        Cell::new("ID"),
-    ]));
-
-    for publication in publications {
-        let nr_of_applicants_string = publication.nr_of_applicants().to_string();
-        let gross_rent_string = publication.rent().to_string();
-        let is_match = if publication.is_match() { "Yes" } else { "No" };
-
-        table.add_row(Row::new(vec![
-            Cell::new(is_match),
-            Cell::new(publication.name()),
-            Cell::new(publication.city()),
-            Cell::new(&nr_of_applicants_string),
-            Cell::new(&gross_rent_string),
-            Cell::new(publication.id()),
-        ]));
+    let Some(tokens) = tokens else {
+        return Ok(status);
+    };
+
+    status.access_expires = Some(tokens.access_token().expires());
+    status.session_expires = Some(tokens.refresh_token().expires());
+    status.session_expired = tokens.refresh_token().has_expired();
+
+    if let Ok(claims) = tokens.claims() {
+        status.name = claims.name().map(String::from);
+        status.email = claims.email().map(String::from);
     }
 
-    Ok(table)
+    if !status.session_expired {
+        let client = config::client()
+            .authenticate(tokens)
+            .on_tokens_changed(secrets::store_tokens);
+
+        match client.get_user().await {
+            Ok(user) => {
+                status.name = Some(user.name());
+                status.email = user.email().map(String::from).or(status.email);
+            }
+            Err(error) => {
+                log::warn!("Failed to get account details: {}", error);
+            }
+        }
+    }
+
+    Ok(status)
 }
 
-pub async fn reply<I: AsRef<str>>(id: I) -> Result<()> {
-    let mut client = secrets::get_client().await?;
+/// The first publications matching the filters, at most as many as their limit.
+pub async fn list(filters: Filters) -> Result<Vec<Publication>> {
+    let limit = filters.limit();
+
+    let mut publications = list_all(filters).await?;
+
+    publications.truncate(limit);
+
+    Ok(publications)
+}
+
+/// All publications matching the filters, ignoring their limit.
+pub async fn list_all(filters: Filters) -> Result<Vec<Publication>> {
+    let logged_in = secrets::get::<_, Tokens>("tokens").is_ok()
+        || secrets::get::<_, secrets::Credentials>("credentials").is_ok();
+
+    let publications = if !logged_in {
+        let client = config::client();
+
+        client.get_publications_list(i64::MAX).await?
+    } else {
+        let client = secrets::get_client().await?;
+
+        client.get_publications_list(i64::MAX).await?
+    };
+
+    let publications = publications
+        .into_iter()
+        .filter(|publication| filters.matches(publication))
+        .collect();
+
+    Ok(publications)
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReplyResult {
+    pub id: String,
+    pub replied: bool,
+}
+
+pub async fn reply<I: AsRef<str>>(id: I) -> Result<ReplyResult> {
+    let client = secrets::get_client().await?;
 
     client.reply_to_publication(id.as_ref()).await?;
 
-    Ok(())
+    Ok(ReplyResult {
+        id: id.as_ref().to_string(),
+        replied: true,
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub selected: bool,
+}
+
+pub fn profiles_list() -> Result<Vec<ProfileSummary>> {
+    let profiles = Profiles::load()?;
+
+    let summaries = profiles
+        .iter()
+        .map(|profile| ProfileSummary {
+            name: profile.name().to_string(),
+            selected: profile.name() == profiles::current(),
+        })
+        .collect();
+
+    Ok(summaries)
+}
+
+pub fn profiles_add<N: AsRef<str>>(name: N) -> Result<ProfileSummary> {
+    let mut profiles = Profiles::load()?;
+
+    profiles.add(name.as_ref())?;
+
+    profiles.save()?;
+
+    Ok(ProfileSummary {
+        name: name.as_ref().to_string(),
+        selected: name.as_ref() == profiles::current(),
+    })
+}
+
+pub fn profiles_remove<N: AsRef<str>>(name: N) -> Result<ProfileSummary> {
+    let mut profiles = Profiles::load()?;
+
+    profiles.remove(name.as_ref())?;
+
+    secrets::delete_for_profile(name.as_ref(), "tokens")?;
+    secrets::delete_for_profile(name.as_ref(), "credentials")?;
+
+    profiles.save()?;
+
+    Ok(ProfileSummary {
+        name: name.as_ref().to_string(),
+        selected: name.as_ref() == profiles::current(),
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct NotifyTestResult {
+    pub notifiers: usize,
+}
+
+/// Send a test notification to every configured notifier.
+pub async fn notify_test() -> Result<NotifyTestResult> {
+    let sinks = &config::get().notify;
+
+    if sinks.is_empty() {
+        return Err(Error::Notify(String::from("no notifiers are configured")));
+    }
+
+    let notifiers = Notifiers::new(sinks)?;
+
+    if let Some(error) = notifiers.send(&Event::Test).await.into_iter().next() {
+        return Err(error);
+    }
+
+    Ok(NotifyTestResult {
+        notifiers: sinks.len(),
+    })
 }
@@ -0,0 +1,337 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sshn_lib::publication::Publication;
+use tokio::time::Instant;
+
+use crate::{
+    config::{self, Filters, WatchRule},
+    error::{Error, Result},
+    notify::{Event, Notifiers},
+    profiles,
+};
+
+/// How many seconds to wait between checks for rules that do not set an interval.
+pub const DEFAULT_INTERVAL: u64 = 300;
+
+/// The publications that were already seen per rule, so they are only notified once.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Seen {
+    rules: HashMap<String, HashSet<String>>,
+}
+
+impl Seen {
+    fn path() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir().ok_or(Error::MissingDataDir)?;
+
+        Ok(data_dir
+            .join("sshn")
+            .join(format!("{}-seen.json", profiles::current())))
+    }
+
+    fn load() -> Result<Self> {
+        let path = Self::path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// Where the publications are fetched from and replied to.
+#[async_trait]
+trait Publications: Sync {
+    /// All publications matching the filters.
+    async fn list(&self, filters: Filters) -> Result<Vec<Publication>>;
+
+    async fn reply(&self, id: &str) -> Result<()>;
+}
+
+/// The SSHN API, using the stored session of the current profile.
+struct Sshn;
+
+#[async_trait]
+impl Publications for Sshn {
+    async fn list(&self, filters: Filters) -> Result<Vec<Publication>> {
+        super::list_all(filters).await
+    }
+
+    async fn reply(&self, id: &str) -> Result<()> {
+        super::reply(id).await?;
+
+        Ok(())
+    }
+}
+
+/// Check for new publications matching the watch rules, notifying about them and replying if the rule says so.
+/// Every event is also passed to `report`, so it can be shown to the user.
+pub async fn watch<F: Fn(&Event)>(once: bool, interval: Option<u64>, report: F) -> Result<()> {
+    let config = config::get();
+
+    let rules = if config.watch.is_empty() {
+        vec![WatchRule {
+            name: String::from("default"),
+            ..WatchRule::default()
+        }]
+    } else {
+        config.watch.clone()
+    };
+
+    let notifiers = Notifiers::new(&config.notify)?;
+
+    if notifiers.is_empty() {
+        log::warn!("No notifiers are configured, new publications will only be shown here");
+    }
+
+    let mut seen = Seen::load()?;
+
+    let mut next_runs = vec![Instant::now(); rules.len()];
+
+    loop {
+        for (rule, next_run) in rules.iter().zip(next_runs.iter_mut()) {
+            if Instant::now() < *next_run {
+                continue;
+            }
+
+            let result = check_rule(&Sshn, rule, &mut seen, &notifiers, &report).await;
+
+            // Failing to save only risks notifying about the same publications again, keep watching.
+            if let Err(error) = seen.save() {
+                log::error!("Failed to save the seen publications: {}", error);
+            }
+
+            match result {
+                Err(error) if once => return Err(error),
+                Err(error) => {
+                    let event = Event::RuleFailed {
+                        rule: rule.name.clone(),
+                        error: error.to_string(),
+                    };
+
+                    report(&event);
+                    notifiers.send(&event).await;
+                }
+                Ok(()) => {}
+            }
+
+            let seconds = interval.or(rule.interval).unwrap_or(DEFAULT_INTERVAL);
+
+            *next_run = Instant::now() + Duration::from_secs(seconds);
+        }
+
+        if once {
+            return Ok(());
+        }
+
+        if let Some(next_run) = next_runs.iter().min() {
+            tokio::time::sleep_until(*next_run).await;
+        }
+    }
+}
+
+async fn check_rule<S: Publications, F: Fn(&Event)>(
+    source: &S,
+    rule: &WatchRule,
+    seen: &mut Seen,
+    notifiers: &Notifiers,
+    report: &F,
+) -> Result<()> {
+    let filters = rule.filters.clone().or(config::get().filters.clone());
+
+    // Publications are filtered after fetching them, so the whole list must be fetched to see every match.
+    let publications = source.list(filters).await?;
+
+    let first_run = !seen.rules.contains_key(&rule.name);
+
+    let seen_ids = seen.rules.entry(rule.name.clone()).or_default();
+
+    // Publications that were already there before the rule was watched are only remembered,
+    // so they are neither notified about nor replied to.
+    if first_run {
+        seen_ids.extend(
+            publications
+                .iter()
+                .map(|publication| publication.id().to_string()),
+        );
+
+        log::info!(
+            "Started watching rule '{}', ignoring {} existing publications",
+            rule.name,
+            publications.len()
+        );
+
+        return Ok(());
+    }
+
+    let new: Vec<Publication> = publications
+        .into_iter()
+        .filter(|publication| !seen_ids.contains(publication.id()))
+        .collect();
+
+    for publication in new.iter() {
+        seen_ids.insert(publication.id().to_string());
+
+        let event = Event::NewPublication {
+            rule: rule.name.clone(),
+            publication: publication.clone(),
+        };
+
+        report(&event);
+        notifiers.send(&event).await;
+    }
+
+    if !rule.auto_reply {
+        return Ok(());
+    }
+
+    let ranking = config::ranking(None)?;
+
+    for ranked in ranking.rank(new) {
+        if rule
+            .min_score
+            .is_some_and(|min_score| ranked.score() < min_score)
+        {
+            continue;
+        }
+
+        let publication = ranked.publication();
+
+        let error = source
+            .reply(publication.id())
+            .await
+            .err()
+            .map(|error| error.to_string());
+
+        let event = Event::Replied {
+            rule: rule.name.clone(),
+            id: publication.id().to_string(),
+            name: publication.name().to_string(),
+            replied: error.is_none(),
+            error,
+        };
+
+        report(&event);
+        notifiers.send(&event).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use serde_json::Value;
+    use sshn_lib::{test_support::*, AuthenticatedClient, Client};
+
+    use super::*;
+    use crate::notify::Sink;
+
+    #[async_trait]
+    impl Publications for AuthenticatedClient {
+        async fn list(&self, filters: Filters) -> Result<Vec<Publication>> {
+            let publications = self.get_publications_list(i64::MAX).await?;
+
+            Ok(publications
+                .into_iter()
+                .filter(|publication| filters.matches(publication))
+                .collect())
+        }
+
+        async fn reply(&self, id: &str) -> Result<()> {
+            Ok(self.reply_to_publication(id).await?)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_rule() {
+        let server = MockServer::start().await.unwrap();
+
+        let client = server.login().await.unwrap();
+
+        let notifiers = Notifiers::new(&[Sink::Webhook {
+            url: format!("{}/hook", server.url()),
+        }])
+        .unwrap();
+
+        let events = RefCell::new(Vec::<Value>::new());
+        let report = |event: &Event| {
+            events
+                .borrow_mut()
+                .push(serde_json::to_value(event).unwrap())
+        };
+        let kinds = || {
+            events
+                .borrow()
+                .iter()
+                .map(|event| event["event"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut rule = WatchRule {
+            name: String::from("rooms"),
+            auto_reply: true,
+            min_score: Some(1000.0),
+            ..WatchRule::default()
+        };
+
+        let mut seen = Seen::default();
+
+        // The publications that are already there are only remembered.
+        check_rule(&client, &rule, &mut seen, &notifiers, &report)
+            .await
+            .unwrap();
+
+        assert!(kinds().is_empty());
+        assert_eq!(seen.rules["rooms"].len(), 2);
+
+        // A new publication is reported, but its score is too low to reply to it.
+        seen.rules.get_mut("rooms").unwrap().remove(PUBLICATION_ID);
+
+        check_rule(&client, &rule, &mut seen, &notifiers, &report)
+            .await
+            .unwrap();
+
+        assert_eq!(kinds(), ["new-publication"]);
+
+        // Without a minimum score, it is replied to as well.
+        rule.min_score = None;
+        seen.rules.get_mut("rooms").unwrap().remove(PUBLICATION_ID);
+
+        check_rule(&client, &rule, &mut seen, &notifiers, &report)
+            .await
+            .unwrap();
+
+        assert_eq!(kinds(), ["new-publication", "new-publication", "replied"]);
+        assert_eq!(events.borrow()[2]["replied"], true);
+
+        let requests = server.requests();
+        let count = |matches: fn(&Request) -> bool| requests.iter().filter(|r| matches(r)).count();
+
+        assert_eq!(
+            count(|request| request.operation_name().as_deref() == Some("PostApplication")),
+            1
+        );
+        assert_eq!(count(|request| request.path == "/hook"), 3);
+    }
+}
@@ -0,0 +1,367 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use sshn_lib::{
+    cassette::Cassette, publication::Publication, ranking::Ranking, rate_limit::RateLimiter,
+    retry::RetryPolicy,
+};
+
+use crate::{
+    error::{Error, Result},
+    notify::Sink,
+    output::OutputFormat,
+    profiles::Settings,
+    secrets::SecretStoreKind,
+};
+
+/// The amount of publications that are listed when no limit is given.
+pub const DEFAULT_LIMIT: usize = 5;
+
+/// The configuration file of the command line interface.
+/// Every option can be overridden by command line arguments or `SSHN_*` environment variables.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub profile: Option<String>,
+    pub output: Option<OutputFormat>,
+    /// The locale used for localized texts returned by the SSHN API, e.g. `nl-NL`.
+    pub locale: Option<String>,
+    pub secret_store: Option<SecretStoreKind>,
+    pub secrets_file: Option<PathBuf>,
+    /// Options for the requests sent to the SSHN API.
+    pub http: Http,
+    /// Options used when logging in.
+    pub auth: Settings,
+    /// Filters applied when listing publications.
+    pub filters: Filters,
+    /// Rules used to rank publications, both when listing them and when replying automatically.
+    pub ranking: Option<Ranking>,
+    /// Rules describing which publications to watch for.
+    pub watch: Vec<WatchRule>,
+    /// Where to send notifications about new publications and automatic replies.
+    pub notify: Vec<Sink>,
+}
+
+impl Config {
+    /// A copy without the tokens and passwords of the notifiers, so it can be shown.
+    pub fn redacted(&self) -> Config {
+        Config {
+            notify: self.notify.iter().map(Sink::redacted).collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Timeouts, retries and rate limiting of the requests sent to the SSHN API.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Http {
+    /// How many seconds a single request may take.
+    pub timeout: Option<u64>,
+    /// How many seconds connecting to the SSHN API may take.
+    pub connect_timeout: Option<u64>,
+    /// How many times failed requests are retried.
+    pub max_retries: Option<u32>,
+    /// How many requests may be sent per second on average, 0 disables the limit.
+    #[serde(deserialize_with = "requests_per_second")]
+    pub requests_per_second: Option<f64>,
+    /// How many requests may be sent at once, before being rate limited.
+    pub burst: Option<u32>,
+}
+
+fn requests_per_second<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error> {
+    match Option::<f64>::deserialize(deserializer)? {
+        Some(rate) if !rate.is_finite() || rate < 0.0 => Err(de::Error::custom(
+            "requests_per_second must be a finite number of at least 0",
+        )),
+        rate => Ok(rate),
+    }
+}
+
+/// Filters to select which publications are interesting.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filters {
+    /// The maximum amount of publications to fetch.
+    pub limit: Option<usize>,
+    /// Only show publications in one of these cities.
+    pub cities: Vec<String>,
+    /// Only show publications with at most this gross rent.
+    pub max_rent: Option<f64>,
+    /// Only show publications you can reply to.
+    pub only_matches: Option<bool>,
+}
+
+impl Filters {
+    /// Fill the options that are not set with the options of the other filters.
+    pub fn or(self, other: Filters) -> Filters {
+        Filters {
+            limit: self.limit.or(other.limit),
+            cities: if self.cities.is_empty() {
+                other.cities
+            } else {
+                self.cities
+            },
+            max_rent: self.max_rent.or(other.max_rent),
+            only_matches: self.only_matches.or(other.only_matches),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT)
+    }
+
+    pub fn matches(&self, publication: &Publication) -> bool {
+        if !self.cities.is_empty()
+            && !self
+                .cities
+                .iter()
+                .any(|city| city.eq_ignore_ascii_case(publication.city()))
+        {
+            return false;
+        }
+
+        if let Some(max_rent) = self.max_rent {
+            if publication.rent() > max_rent {
+                return false;
+            }
+        }
+
+        if self.only_matches.unwrap_or(false) && !publication.is_match() {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// A rule describing which publications to watch for.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchRule {
+    pub name: String,
+    /// How many seconds to wait between checking for new publications.
+    pub interval: Option<u64>,
+    /// Automatically reply to publications matching this rule.
+    pub auto_reply: bool,
+    /// Only reply automatically to publications with at least this score, according to the ranking rules.
+    pub min_score: Option<f64>,
+    pub filters: Filters,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+static CASSETTE: OnceLock<Arc<Cassette>> = OnceLock::new();
+
+/// Shared by all clients, so they are rate limited together.
+static RATE_LIMITER: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+
+/// The default location of the configuration file, in the configuration directory of the user.
+pub fn default_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("sshn").join("config.toml"))
+}
+
+/// Load the configuration file. A missing file is only an error when its path was given explicitly.
+pub fn load(path: Option<&Path>) -> Result<Config> {
+    let (path, explicit) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+
+    if !explicit && !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+
+    toml::from_str(&contents).map_err(|error| Error::Config(path, error))
+}
+
+/// Set the configuration used by this invocation. Should be called once, before it is used.
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        log::warn!("Configuration was already loaded");
+    }
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// The rules used to rank publications: the given rules file, the configured rules,
+/// or ranking by the chance of getting a publication when neither is set.
+pub fn ranking(rules: Option<&Path>) -> Result<Ranking> {
+    match rules {
+        Some(path) => {
+            let contents = fs::read_to_string(path)?;
+
+            Ranking::from_toml(contents).map_err(|error| Error::Rules(path.to_path_buf(), error))
+        }
+        None => Ok(get().ranking.clone().unwrap_or_else(Ranking::by_chance)),
+    }
+}
+
+/// Record or replay the requests of every client created after this. Should be called once.
+pub fn use_cassette(cassette: Cassette) {
+    if CASSETTE.set(Arc::new(cassette)).is_err() {
+        log::warn!("Cassette was already set");
+    }
+}
+
+/// Create a client for the SSHN API, using the configured locale, request options and the cassette, if any.
+pub fn client() -> sshn_lib::UnAuthenticatedClient {
+    let config = get();
+
+    let mut client = sshn_lib::UnAuthenticatedClient::new(None);
+
+    if let Some(locale) = config.locale.as_ref() {
+        client = client.locale(locale);
+    }
+
+    if let Some(timeout) = config.http.timeout {
+        client = client.timeout(Duration::from_secs(timeout));
+    }
+
+    if let Some(connect_timeout) = config.http.connect_timeout {
+        client = client.connect_timeout(Duration::from_secs(connect_timeout));
+    }
+
+    if let Some(max_retries) = config.http.max_retries {
+        client = client.retry_policy(RetryPolicy::default().max_retries(max_retries));
+    }
+
+    if config.http.requests_per_second == Some(0.0) {
+        client = client.without_rate_limit();
+    } else {
+        let rate_limiter = RATE_LIMITER.get_or_init(|| {
+            let default = RateLimiter::default();
+
+            match (config.http.burst, config.http.requests_per_second) {
+                (None, None) => Arc::new(default),
+                (burst, per_second) => Arc::new(RateLimiter::new(
+                    burst.unwrap_or(default.capacity()),
+                    per_second.unwrap_or(default.per_second()),
+                )),
+            }
+        });
+
+        client = client.rate_limiter(Arc::clone(rate_limiter));
+    }
+
+    if let Some(cassette) = CASSETTE.get() {
+        client = client.cassette(Arc::clone(cassette));
+    }
+
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(
+            r#"
+            output = "json"
+            locale = "nl-NL"
+
+            [auth]
+            webdriver = "gecko"
+            webdriver_port = 4445
+
+            [filters]
+            limit = 20
+            cities = ["Nijmegen"]
+
+            [[watch]]
+            name = "cheap"
+            auto_reply = true
+            filters = { max_rent = 450.0 }
+
+            [http]
+            timeout = 10
+            requests_per_second = 0.5
+
+            [[notify]]
+            type = "ntfy"
+            url = "https://ntfy.sh/rooms"
+
+            [ranking.constraints]
+            max_higher_priority = 10
+
+            [[ranking.bonuses]]
+            is_match = true
+            score = 10.0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.output, Some(OutputFormat::Json));
+        assert_eq!(config.auth.webdriver_port, Some(4445));
+        assert_eq!(config.filters.limit(), 20);
+        assert_eq!(config.watch[0].filters.max_rent, Some(450.0));
+        assert!(matches!(config.notify[0], Sink::Ntfy { .. }));
+        assert_eq!(
+            config.ranking.unwrap().constraints.max_higher_priority,
+            Some(10)
+        );
+
+        let filters = Filters::default().or(config.filters);
+
+        assert_eq!(filters.cities, ["Nijmegen"]);
+    }
+
+    #[test]
+    fn test_redacted() {
+        let config: Config = toml::from_str(
+            r#"
+            [[notify]]
+            type = "telegram"
+            token = "bot-token"
+            chat_id = "42"
+
+            [[notify]]
+            type = "smtp"
+            host = "smtp.example.org"
+            password = "hunter2"
+            from = "me@example.org"
+            to = "me@example.org"
+            "#,
+        )
+        .unwrap();
+
+        let shown = toml::to_string(&config.redacted()).unwrap();
+
+        assert!(shown.contains("42") && shown.contains("smtp.example.org"));
+        assert!(!shown.contains("bot-token") && !shown.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_unknown_option() {
+        assert!(toml::from_str::<Config>("unknown = true").is_err());
+    }
+
+    #[test]
+    fn test_invalid_requests_per_second() {
+        for rate in ["-1.0", "nan", "inf"] {
+            let config = format!("[http]\nrequests_per_second = {}", rate);
+
+            assert!(toml::from_str::<Config>(&config).is_err());
+        }
+
+        let config: Config = toml::from_str("[http]\nrequests_per_second = 0.0").unwrap();
+
+        assert_eq!(config.http.requests_per_second, Some(0.0));
+    }
+}
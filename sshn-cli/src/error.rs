@@ -11,11 +11,25 @@ pub enum Error {
     #[error("SSHN Api did not return valid authorization code")]
     MissingAuthCode,
 
-    #[error("Missing username and password credentials")]
+    #[error(
+        "Login portal did not redirect back to SSHN in time{}",
+        .0.as_ref()
+            .map(|dir| format!(", saved the page to '{}'", dir.display()))
+            .unwrap_or_else(|| String::from(", use --debug-dir to save the page"))
+    )]
+    CallbackTimeout(Option<std::path::PathBuf>),
+
+    #[error("Login portal rejected the login: {0}")]
+    LoginRejected(String),
+
+    #[error("Not logged in, please login first")]
     MissingCredentials,
 
-    #[error("Failed to start web driver")]
-    WebDriverStart,
+    #[error("Session expired, please login again")]
+    SessionExpired,
+
+    #[error("Failed to start web driver: {0}")]
+    WebDriverStart(String),
 
     #[error("Failed to create new web driver session: {0}")]
     NewWebDriverSession(#[from] fantoccini::error::NewSessionError),
@@ -23,14 +37,161 @@ pub enum Error {
     #[error("Failed communicating with browser: {0}")]
     HeadlessBrowser(#[from] fantoccini::error::CmdError),
 
+    #[error("No secret stored for '{0}'")]
+    MissingSecret(String),
+
+    #[error("No location for the secrets file, please specify one")]
+    MissingSecretsFile,
+
+    #[error("Missing passphrase for the secrets file, set SSHN_SECRETS_PASSPHRASE")]
+    MissingPassphrase,
+
+    #[error("Failed to encrypt secrets")]
+    Encryption,
+
+    #[error("Failed to decrypt secrets, is the passphrase correct?")]
+    Decryption,
+
+    #[error("Invalid profile name '{0}', only lowercase letters, digits and '-' are allowed")]
+    InvalidProfileName(String),
+
+    #[error("Profile '{0}' already exists")]
+    ProfileExists(String),
+
+    #[error("Profile '{0}' does not exist")]
+    UnknownProfile(String),
+
+    #[error("The default profile cannot be removed")]
+    RemoveDefaultProfile,
+
+    #[error("Could not determine the data directory of the user")]
+    MissingDataDir,
+
     #[error("Keyring error: {0}")]
     Keyring(#[from] keyring::Error),
 
     #[error("Failed to serialize/deserialize JSON: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("Invalid configuration file '{}': {1}", .0.display())]
+    Config(std::path::PathBuf, toml::de::Error),
+
+    #[error("Failed to send notification: {0}")]
+    Notify(String),
+
+    #[error("Error sending HTTP request: {0}")]
+    HttpRequest(#[from] reqwest::Error),
+
+    #[error("Failed to parse url: {0}")]
+    ParseUrl(#[from] url::ParseError),
+
+    #[error("Invalid ranking rules '{}': {1}", .0.display())]
+    Rules(std::path::PathBuf, sshn_lib::error::Error),
+
+    #[error("Failed to write TOML: {0}")]
+    Toml(#[from] toml::ser::Error),
+
+    #[error("Failed to write CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Failed to write YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// The exit codes used by the command line interface, so scripts can tell why a command failed.
+///
+/// | Code | Meaning                                                  |
+/// |------|----------------------------------------------------------|
+/// | 0    | Success                                                  |
+/// | 1    | Any error not listed below                               |
+/// | 2    | Invalid command line arguments, reported by clap         |
+/// | 3    | Not logged in, or the session expired                    |
+/// | 4    | The SSHN API returned an error or an unexpected response |
+/// | 5    | Network error while talking to the SSHN API              |
+/// | 6    | Failed to start or control the web driver or browser     |
+/// | 7    | Failed to access the secret store                        |
+/// | 8    | Invalid or unknown profile                               |
+/// | 9    | Failed to read or write local files or output            |
+pub mod exit_code {
+    pub const GENERAL: u8 = 1;
+    pub const UNAUTHENTICATED: u8 = 3;
+    pub const API: u8 = 4;
+    pub const NETWORK: u8 = 5;
+    pub const BROWSER: u8 = 6;
+    pub const SECRETS: u8 = 7;
+    pub const PROFILE: u8 = 8;
+    pub const IO: u8 = 9;
+}
+
+/// Whether the request failed before a response was received, unlike e.g. failing to decode a response.
+fn is_network_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout() || error.is_request()
+}
+
+impl Error {
+    /// The exit code the command line interface should exit with when this error occurs.
+    pub fn exit_code(&self) -> u8 {
+        use sshn_lib::error::Error as LibError;
+
+        match self {
+            Error::SshnLib(error) => match error {
+                LibError::TokenExpired | LibError::Unauthorized | LibError::MissingRefreshToken => {
+                    exit_code::UNAUTHENTICATED
+                }
+                error if error.is_unauthorized() => exit_code::UNAUTHENTICATED,
+                LibError::HttpRequest(error) if is_network_error(error) => exit_code::NETWORK,
+                LibError::HttpRequest(_)
+                | LibError::Api(_)
+                | LibError::MissingPublications
+                | LibError::MissingUser
+                | LibError::MissingReplyState
+                | LibError::GraphQL(_)
+                | LibError::NoAuthUrl
+                | LibError::InvalidJwt
+                | LibError::Json(_) => exit_code::API,
+                LibError::Io(_) | LibError::Cassette(_) => exit_code::IO,
+                _ => exit_code::GENERAL,
+            },
+
+            Error::MissingCredentials | Error::SessionExpired | Error::LoginRejected(_) => {
+                exit_code::UNAUTHENTICATED
+            }
+
+            Error::HttpRequest(error) if is_network_error(error) => exit_code::NETWORK,
+            Error::Notify(_) | Error::HttpRequest(_) | Error::ParseUrl(_) => exit_code::GENERAL,
+
+            Error::MissingLoginUrl | Error::MissingAuthCode => exit_code::API,
+
+            Error::CallbackTimeout(_)
+            | Error::WebDriverStart(_)
+            | Error::NewWebDriverSession(_)
+            | Error::HeadlessBrowser(_) => exit_code::BROWSER,
+
+            Error::MissingSecret(_)
+            | Error::MissingSecretsFile
+            | Error::MissingPassphrase
+            | Error::Encryption
+            | Error::Decryption
+            | Error::Keyring(_) => exit_code::SECRETS,
+
+            Error::InvalidProfileName(_)
+            | Error::ProfileExists(_)
+            | Error::UnknownProfile(_)
+            | Error::RemoveDefaultProfile => exit_code::PROFILE,
+
+            Error::MissingDataDir
+            | Error::Config(_, _)
+            | Error::Rules(_, _)
+            | Error::Toml(_)
+            | Error::Json(_)
+            | Error::Csv(_)
+            | Error::Yaml(_)
+            | Error::Io(_) => exit_code::IO,
+        }
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
@@ -1,13 +1,25 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use chrono::Local;
 use clap::{Parser, Subcommand};
 use rpassword::read_password;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 mod auth;
 mod commands;
+mod config;
 mod error;
+mod notify;
+mod output;
+mod profiles;
 mod secrets;
+mod webdriver;
 
-use auth::AuthOptions;
+use config::Filters;
+use output::OutputFormat;
+use profiles::Settings;
+use secrets::SecretStoreKind;
+use sshn_lib::cassette::Cassette;
 
 macro_rules! show {
     ($($arg:tt)*) => ({
@@ -15,10 +27,29 @@ macro_rules! show {
 
         let app_name = "[SSHN-CLI]".blue().bold();
 
-        println!("{} {}", app_name, format!($($arg)*));
+        // Keep stdout clean for the results when they are read by other programs.
+        if output::is_machine_readable() {
+            eprintln!("{} {}", app_name, format!($($arg)*));
+        } else {
+            println!("{} {}", app_name, format!($($arg)*));
+        }
     });
 }
 
+/// Print an error to stderr, then return the exit code belonging to it.
+macro_rules! fail {
+    ($context:expr, $error:expr) => {{
+        use colored::*;
+
+        let app_name = "[SSHN-CLI]".red().bold();
+        let error = $error;
+
+        eprintln!("{} {}:\n\t {}", app_name, $context, error);
+
+        return ExitCode::from(error.exit_code());
+    }};
+}
+
 /// SSHN command line interface.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -26,6 +57,44 @@ macro_rules! show {
 struct Args {
     #[command(subcommand)]
     command: Commands,
+
+    /// The configuration file to use, instead of the one in the configuration directory.
+    #[arg(long, global = true, env = "SSHN_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Where to store the tokens and credentials.
+    #[arg(long, global = true, value_enum, env = "SSHN_SECRET_STORE")]
+    secret_store: Option<SecretStoreKind>,
+
+    /// The location of the secrets file, when using the file secret store.
+    #[arg(long, global = true, env = "SSHN_SECRETS_FILE")]
+    secrets_file: Option<PathBuf>,
+
+    /// The format results are printed in.
+    #[arg(short, long, global = true, value_enum, env = "SSHN_OUTPUT")]
+    output: Option<OutputFormat>,
+
+    /// The profile to use, each profile has its own account and settings.
+    #[arg(long, global = true, env = "SSHN_PROFILE")]
+    profile: Option<String>,
+
+    /// The locale used for localized texts returned by the SSHN API, e.g. `nl-NL`.
+    #[arg(long, global = true, env = "SSHN_LOCALE")]
+    locale: Option<String>,
+
+    /// Store every request to and response from the SSHN API in this directory, with tokens redacted.
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        env = "SSHN_RECORD",
+        conflicts_with = "replay"
+    )]
+    record: Option<PathBuf>,
+
+    /// Answer requests to the SSHN API with the responses recorded in this directory.
+    #[arg(long, global = true, value_name = "DIR", env = "SSHN_REPLAY")]
+    replay: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -41,33 +110,159 @@ pub enum Commands {
         password: Option<String>,
 
         /// The login portal base url.
-        #[arg(short, long)]
+        #[arg(short, long, env = "SSHN_LOGIN_URL")]
         login_url: Option<String>,
 
         /// The web driver to use to connect to the browser.
-        #[arg(short, long, default_value_t, value_enum)]
-        webdriver: WebDriver,
+        #[arg(short, long, value_enum, env = "SSHN_WEBDRIVER")]
+        webdriver: Option<WebDriver>,
 
         /// Whether to auto start the web driver.
         #[arg(long)]
         auto_start_webdriver: bool,
 
         /// The port used to connect to the webdriver.
-        #[arg(long)]
+        #[arg(long, env = "SSHN_WEBDRIVER_PORT")]
         webdriver_port: Option<u16>,
+
+        /// Store the password, so a new login can be done automatically once the session expires.
+        #[arg(long)]
+        remember_password: bool,
+
+        /// Connect to an already running web driver at this url, e.g. a remote Selenium grid.
+        #[arg(long, env = "SSHN_WEBDRIVER_URL")]
+        webdriver_url: Option<String>,
+
+        /// Path to the web driver binary, instead of looking it up in `PATH`.
+        #[arg(long, env = "SSHN_WEBDRIVER_PATH")]
+        webdriver_path: Option<PathBuf>,
+
+        /// Show the browser window instead of running it headless.
+        #[arg(long)]
+        no_headless: bool,
+
+        /// The user agent the browser should use.
+        #[arg(long)]
+        user_agent: Option<String>,
+
+        /// The proxy the browser should use, in the form `host:port`.
+        #[arg(long)]
+        proxy: Option<String>,
+
+        /// How many seconds to wait for a started web driver to accept connections.
+        #[arg(long)]
+        webdriver_timeout: Option<u64>,
+
+        /// How many seconds to wait for the login portal to redirect back to SSHN.
+        #[arg(long)]
+        login_timeout: Option<u64>,
+
+        /// Save a screenshot and the page source to this directory when the login fails.
+        /// Nothing is saved by default, as the page can contain personal details.
+        #[arg(long)]
+        debug_dir: Option<PathBuf>,
     },
 
+    /// Logout of the SSHN API, removing the stored tokens.
+    Logout {
+        /// Also remove the stored username and password.
+        #[arg(long)]
+        forget_credentials: bool,
+    },
+
+    /// Show whether you are logged in and when the session expires.
+    #[command(alias = "whoami")]
+    Status,
+
     /// List the currently open publications.
     List {
+        /// The maximum amount of publications to fetch.
         #[arg(short, long)]
         limit: Option<usize>,
+
+        /// Only show publications in this city, can be given multiple times.
+        #[arg(long = "city")]
+        cities: Vec<String>,
+
+        /// Only show publications with at most this gross rent.
+        #[arg(long)]
+        max_rent: Option<f64>,
+
+        /// Only show publications you can reply to.
+        #[arg(long)]
+        only_matches: bool,
+
+        /// Sort publications by their score, according to the ranking rules.
+        #[arg(long)]
+        rank: bool,
+
+        /// A TOML file with the ranking rules, implies --rank.
+        #[arg(long, env = "SSHN_RULES")]
+        rules: Option<PathBuf>,
     },
 
     /// Reply to a publication with a given id.
     Reply { id: String },
+
+    /// Watch for new publications matching the watch rules in the configuration file,
+    /// sending notifications and replying automatically when a rule says so.
+    Watch {
+        /// Check every rule once, then exit.
+        #[arg(long)]
+        once: bool,
+
+        /// How many seconds to wait between checks, overrides the interval of every rule.
+        #[arg(short, long, env = "SSHN_WATCH_INTERVAL")]
+        interval: Option<u64>,
+    },
+
+    /// Manage the notifications sent by the watch command.
+    Notify {
+        #[command(subcommand)]
+        command: NotifyCommands,
+    },
+
+    /// Show the configuration file.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Manage the profiles used to store multiple accounts.
+    Profiles {
+        #[command(subcommand)]
+        command: ProfilesCommands,
+    },
 }
 
-#[derive(clap::ValueEnum, Serialize, Debug, Clone, Default)]
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Show the configuration, merged with the command line arguments, without the secrets of notifiers.
+    Show,
+
+    /// Show the location of the configuration file.
+    Path,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfilesCommands {
+    /// List the known profiles.
+    List,
+
+    /// Add a new profile.
+    Add { name: String },
+
+    /// Remove a profile, together with its stored tokens and credentials.
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NotifyCommands {
+    /// Send a test notification to every configured notifier.
+    Test,
+}
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum WebDriver {
     #[default]
@@ -76,11 +271,61 @@ pub enum WebDriver {
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     env_logger::init();
 
     let args = Args::parse();
 
+    let mut config = match config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(error) => fail!("Error loading configuration", error),
+    };
+
+    // Command line arguments and environment variables take precedence over the configuration file.
+    config.output = args.output.or(config.output);
+    config.profile = args.profile.or(config.profile);
+    config.locale = args.locale.or(config.locale);
+    config.secret_store = args.secret_store.or(config.secret_store);
+    config.secrets_file = args.secrets_file.or(config.secrets_file);
+
+    output::select(config.output.unwrap_or_default());
+
+    let profile = config
+        .profile
+        .clone()
+        .unwrap_or(String::from(profiles::DEFAULT_PROFILE));
+
+    // Profiles can still be managed when the selected profile does not exist yet.
+    let allow_missing = matches!(args.command, Commands::Profiles { .. });
+
+    if let Err(error) = profiles::select(profile, allow_missing) {
+        fail!("Error selecting profile", error)
+    }
+
+    // Replayed token responses are redacted, so they must not replace the tokens in the secret store.
+    if let Err(error) = secrets::init(
+        config.secret_store.unwrap_or_default(),
+        config.secrets_file.clone(),
+        args.replay.is_some(),
+    ) {
+        fail!("Error opening secret store", error)
+    }
+
+    config::init(config);
+
+    let cassette = match (args.record, args.replay) {
+        (Some(dir), _) => Some(Cassette::record(dir)),
+        (None, Some(dir)) => Some(Cassette::replay(dir)),
+        (None, None) => None,
+    };
+
+    if let Some(cassette) = cassette {
+        match cassette {
+            Ok(cassette) => config::use_cassette(cassette),
+            Err(error) => fail!("Error opening cassette", error::Error::from(error)),
+        }
+    }
+
     match args.command {
         Commands::Login {
             username,
@@ -89,59 +334,168 @@ async fn main() {
             webdriver,
             auto_start_webdriver,
             webdriver_port,
+            remember_password,
+            webdriver_url,
+            webdriver_path,
+            no_headless,
+            user_agent,
+            proxy,
+            webdriver_timeout,
+            login_timeout,
+            debug_dir,
         } => {
             let password = match password {
                 Some(pass) => pass,
                 None => {
                     show!("Enter the password of your {} account: ", "SSHN".bold());
-                    let password = read_password().expect("Failed to read password");
-
-                    password
+                    read_password().expect("Failed to read password")
                 }
             };
 
             show!("Logging in as user '{}'", username.bold().green());
 
-            let mut auth_options = AuthOptions::default()
-                .webdriver(webdriver)
-                .auto_start_webdriver(auto_start_webdriver);
+            let settings = Settings {
+                webdriver,
+                webdriver_port,
+                auto_start_webdriver: auto_start_webdriver.then_some(true),
+                login_url,
+                webdriver_url,
+                webdriver_path,
+                headless: no_headless.then_some(false),
+                user_agent,
+                proxy,
+                webdriver_timeout,
+                login_timeout,
+                debug_dir,
+            };
 
-            if let Some(webdriver_port) = webdriver_port {
-                auth_options = auth_options.webdriver_port(webdriver_port);
-            }
+            match commands::login(&username, &password, settings, remember_password).await {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
+                Ok(result) => {
+                    let identity = result.name.or(result.email).unwrap_or(result.username);
 
-            if let Some(login_url) = login_url {
-                auth_options = auth_options.login_base_url(login_url);
-            }
-
-            match commands::login(&username, &password, auth_options).await {
-                Ok(_) => {
                     show!(
                         "Succesfully logged in as user '{}'.",
-                        username.bold().green()
+                        identity.bold().green()
                     )
                 }
                 Err(error) => {
-                    show!("Error logging in:\n\t {}", error);
+                    fail!("Error logging in", error)
                 }
             }
         }
 
-        Commands::List { limit } => {
-            match commands::list(limit.unwrap_or(5)).await {
-                Ok(table) => {
-                    table.printstd();
+        Commands::Logout { forget_credentials } => {
+            match commands::logout(forget_credentials).await {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
+                Ok(_) => {
+                    show!("Successfully logged out.")
                 }
                 Err(error) => {
-                    show!("Error listing publications:\n\t {}", error);
+                    fail!("Error logging out", error)
                 }
             };
         }
 
+        Commands::Status => {
+            match commands::status().await {
+                Ok(status) if output::is_machine_readable() => return print_record(&status),
+                Ok(status) => {
+                    if status.logged_in {
+                        let identity = match (status.name, status.email) {
+                            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+                            (name, email) => name.or(email).unwrap_or(String::from("unknown user")),
+                        };
+
+                        show!("Logged in as '{}'.", identity.bold().green());
+
+                        if let Some(access_expires) = status.access_expires {
+                            show!(
+                                "Access token expires at {}.",
+                                access_expires.with_timezone(&Local)
+                            );
+                        }
+
+                        if let Some(session_expires) = status.session_expires {
+                            show!(
+                                "Session expires at {}.",
+                                session_expires.with_timezone(&Local)
+                            );
+                        }
+
+                        if status.session_expired {
+                            show!("{}", "The session has expired, please login again.".red());
+                        }
+                    } else {
+                        show!("Not logged in.");
+                    }
+
+                    if status.has_credentials {
+                        show!("Credentials are stored for automatic re-login.");
+                    } else {
+                        show!("No credentials are stored.");
+                    }
+                }
+                Err(error) => {
+                    fail!("Error getting session status", error)
+                }
+            };
+        }
+
+        Commands::List {
+            limit,
+            cities,
+            max_rent,
+            only_matches,
+            rank,
+            rules,
+        } => {
+            let filters = Filters {
+                limit,
+                cities,
+                max_rent,
+                only_matches: only_matches.then_some(true),
+            }
+            .or(config::get().filters.clone());
+
+            if !rank && rules.is_none() {
+                return match commands::list(filters).await {
+                    Ok(publications) => print_list(&publications),
+                    Err(error) => {
+                        fail!("Error listing publications", error)
+                    }
+                };
+            }
+
+            let ranking = match config::ranking(rules.as_deref()) {
+                Ok(ranking) => ranking,
+                Err(error) => {
+                    fail!("Error loading ranking rules", error)
+                }
+            };
+
+            let limit = filters.limit();
+
+            // Rank every matching publication, so the best ones are not cut off by the limit.
+            let publications = match commands::list_all(filters).await {
+                Ok(publications) => publications,
+                Err(error) => {
+                    fail!("Error listing publications", error)
+                }
+            };
+
+            let mut ranked = ranking.rank(publications);
+
+            ranked.truncate(limit);
+
+            return print_list(&ranked);
+        }
+
         Commands::Reply { id } => {
             show!("Replying to publication...");
 
             match commands::reply(&id).await {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
                 Ok(_) => {
                     show!(
                         "Successfully replied to publication with id '{}'.",
@@ -149,9 +503,109 @@ async fn main() {
                     )
                 }
                 Err(error) => {
-                    show!("Error replying to publication:\n\t {}", error);
+                    fail!("Error replying to publication", error)
+                }
+            };
+        }
+
+        Commands::Watch { once, interval } => {
+            show!("Watching for new publications...");
+
+            let events = output::RecordStream::new(notify::Event::COLUMNS.iter().copied());
+
+            let report = |event: &notify::Event| {
+                if output::is_machine_readable() {
+                    if let Err(error) = events.print(event) {
+                        log::warn!("Failed to print event: {}", error);
+                    }
+                } else {
+                    show!("{}: {}", event.title().bold(), event.message());
                 }
             };
+
+            if let Err(error) = commands::watch(once, interval, report).await {
+                fail!("Error watching publications", error)
+            }
         }
+
+        Commands::Notify { command } => match command {
+            NotifyCommands::Test => match commands::notify_test().await {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
+                Ok(result) => {
+                    show!(
+                        "Sent a test notification to {} notifier(s).",
+                        result.notifiers.to_string().bold().green()
+                    )
+                }
+                Err(error) => {
+                    fail!("Error sending test notification", error)
+                }
+            },
+        },
+
+        Commands::Config { command } => match command {
+            ConfigCommands::Show => {
+                let config = config::get().redacted();
+
+                match output::format() {
+                    OutputFormat::Table => match toml::to_string_pretty(&config) {
+                        Ok(config) => println!("{}", config),
+                        Err(error) => {
+                            fail!("Error showing configuration", error::Error::from(error))
+                        }
+                    },
+                    _ => return print_record(&config),
+                }
+            }
+            ConfigCommands::Path => match args.config.or_else(config::default_path) {
+                Some(path) => println!("{}", path.display()),
+                None => show!("Could not determine the configuration directory."),
+            },
+        },
+
+        Commands::Profiles { command } => match command {
+            ProfilesCommands::List => match commands::profiles_list() {
+                Ok(profiles) => return print_list(&profiles),
+                Err(error) => {
+                    fail!("Error listing profiles", error)
+                }
+            },
+            ProfilesCommands::Add { name } => match commands::profiles_add(&name) {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
+                Ok(_) => {
+                    show!("Added profile '{}'.", name.bold().green())
+                }
+                Err(error) => {
+                    fail!("Error adding profile", error)
+                }
+            },
+            ProfilesCommands::Remove { name } => match commands::profiles_remove(&name) {
+                Ok(result) if output::is_machine_readable() => return print_record(&result),
+                Ok(_) => {
+                    show!("Removed profile '{}'.", name.bold().green())
+                }
+                Err(error) => {
+                    fail!("Error removing profile", error)
+                }
+            },
+        },
     }
+
+    ExitCode::SUCCESS
+}
+
+fn print_list<T: output::Tabular>(records: &[T]) -> ExitCode {
+    if let Err(error) = output::print_list(records) {
+        fail!("Error printing results", error)
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn print_record<T: Serialize>(record: &T) -> ExitCode {
+    if let Err(error) = output::print_record(record) {
+        fail!("Error printing result", error)
+    }
+
+    ExitCode::SUCCESS
 }
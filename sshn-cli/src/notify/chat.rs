@@ -0,0 +1,236 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::error::{Error, Result};
+
+use super::{send, Event, Notifier};
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+fn chat_message(event: &Event) -> String {
+    format!("{}\n{}", event.title(), event.message())
+}
+
+/// Sends events to a chat using a Telegram bot.
+pub struct TelegramNotifier {
+    token: String,
+    chat_id: String,
+    api_url: String,
+    http_client: reqwest::Client,
+}
+
+impl TelegramNotifier {
+    pub fn new(
+        token: String,
+        chat_id: String,
+        api_url: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Self {
+        Self {
+            token,
+            chat_id,
+            api_url: api_url.unwrap_or(TELEGRAM_API_URL.to_string()),
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.api_url.trim_end_matches('/'),
+            self.token
+        );
+
+        let request = self.http_client.post(url).json(&json!({
+            "chat_id": self.chat_id,
+            "text": chat_message(event),
+        }));
+
+        send(request).await
+    }
+}
+
+/// Sends events to a Discord channel using a webhook.
+pub struct DiscordNotifier {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(url: String, http_client: reqwest::Client) -> Self {
+        Self { url, http_client }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let request = self
+            .http_client
+            .post(&self.url)
+            .json(&json!({ "content": chat_message(event) }));
+
+        send(request).await
+    }
+}
+
+/// Sends events to a Matrix room.
+pub struct MatrixNotifier {
+    homeserver: String,
+    room_id: String,
+    access_token: String,
+    http_client: reqwest::Client,
+}
+
+impl MatrixNotifier {
+    pub fn new(
+        homeserver: String,
+        room_id: String,
+        access_token: String,
+        http_client: reqwest::Client,
+    ) -> Self {
+        Self {
+            homeserver,
+            room_id,
+            access_token,
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        // Matrix uses the transaction id to deduplicate retried requests.
+        let transaction_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+
+        let mut url = url::Url::parse(&self.homeserver)?;
+
+        url.path_segments_mut()
+            .map_err(|_| Error::Notify(String::from("invalid Matrix homeserver url")))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &transaction_id.to_string(),
+            ]);
+
+        let request = self
+            .http_client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "msgtype": "m.text",
+                "body": chat_message(event),
+            }));
+
+        send(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use sshn_lib::test_support::MockServer;
+
+    use super::*;
+    use crate::notify::http_client;
+
+    #[tokio::test]
+    async fn test_telegram() {
+        let server = MockServer::start().await.unwrap();
+
+        TelegramNotifier::new(
+            String::from("token"),
+            String::from("42"),
+            Some(server.url().to_string()),
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+
+        assert_eq!(request.path, "/bottoken/sendMessage");
+        assert_eq!(body["chat_id"], "42");
+        assert_eq!(body["text"], chat_message(&Event::Test));
+    }
+
+    #[tokio::test]
+    async fn test_error_without_token() {
+        let server = MockServer::start().await.unwrap();
+
+        server.fail_next([500]);
+
+        let error = TelegramNotifier::new(
+            String::from("secret-token"),
+            String::from("42"),
+            Some(server.url().to_string()),
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap_err();
+
+        assert!(error.to_string().contains("500"));
+        assert!(!error.to_string().contains("secret-token"));
+    }
+
+    #[tokio::test]
+    async fn test_discord() {
+        let server = MockServer::start().await.unwrap();
+
+        DiscordNotifier::new(
+            format!("{}/api/webhooks/1/token", server.url()),
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+
+        assert_eq!(request.path, "/api/webhooks/1/token");
+        assert_eq!(body["content"], chat_message(&Event::Test));
+    }
+
+    #[tokio::test]
+    async fn test_matrix() {
+        let server = MockServer::start().await.unwrap();
+
+        MatrixNotifier::new(
+            server.url().to_string(),
+            String::from("!room:example.org"),
+            String::from("token"),
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+
+        assert_eq!(request.method, "PUT");
+        assert!(request
+            .path
+            .starts_with("/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/"));
+        assert_eq!(request.header("authorization"), Some("Bearer token"));
+        assert!(request.body.contains(r#""msgtype":"m.text""#));
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::error::{Error, Result};
+
+use super::{Event, Notifier, TIMEOUT};
+
+/// Shows events as desktop notifications, using libnotify's `notify-send`.
+pub struct DesktopNotifier {
+    program: String,
+}
+
+impl Default for DesktopNotifier {
+    fn default() -> Self {
+        Self {
+            program: String::from("notify-send"),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let status = Command::new(&self.program)
+            .arg("--app-name=sshn-cli")
+            .arg(event.title())
+            .arg(event.message())
+            .kill_on_drop(true)
+            .status();
+
+        let status = tokio::time::timeout(Duration::from_secs(TIMEOUT), status)
+            .await
+            .map_err(|_| Error::Notify(format!("{} timed out", self.program)))?
+            .map_err(|error| Error::Notify(format!("failed to run {}: {}", self.program, error)))?;
+
+        if !status.success() {
+            return Err(Error::Notify(format!(
+                "{} exited with {}",
+                self.program, status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier(program: &str) -> DesktopNotifier {
+        DesktopNotifier {
+            program: String::from(program),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_desktop() {
+        notifier("true").notify(&Event::Test).await.unwrap();
+
+        let error = notifier("false").notify(&Event::Test).await.unwrap_err();
+
+        assert!(error.to_string().contains("false exited with"));
+
+        assert!(notifier("sshn-missing-notify-send")
+            .notify(&Event::Test)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::error::{Error, Result};
+
+use super::{Event, Notifier, TIMEOUT};
+
+/// Emails events using an SMTP server.
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+}
+
+impl SmtpNotifier {
+    /// Connects using STARTTLS, unless `insecure` is set.
+    pub fn new(
+        host: &str,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+        insecure: bool,
+        from: &str,
+        to: &str,
+    ) -> Result<Self> {
+        let mut builder = if insecure {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(smtp_error)?
+        };
+
+        builder = builder.timeout(Some(Duration::from_secs(TIMEOUT)));
+
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+
+        if let Some(username) = username {
+            builder = builder.credentials(Credentials::new(username, password.unwrap_or_default()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: parse_mailbox(from)?,
+            to: parse_mailbox(to)?,
+        })
+    }
+}
+
+fn smtp_error<E: std::fmt::Display>(error: E) -> Error {
+    Error::Notify(format!("SMTP error: {}", error))
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|error| Error::Notify(format!("invalid email address '{}': {}", address, error)))
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(event.title())
+            .body(event.message())
+            .map_err(smtp_error)?;
+
+        self.transport.send(message).await.map_err(smtp_error)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Answer a single SMTP session, returns the commands and message that were received.
+    async fn serve_session(listener: TcpListener) -> Vec<String> {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+
+        let mut received = Vec::new();
+        let mut in_data = false;
+
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let reply: &[u8] = match line.as_str() {
+                "." if in_data => {
+                    in_data = false;
+                    b"250 Queued\r\n"
+                }
+                _ if in_data => {
+                    received.push(line);
+                    continue;
+                }
+                "DATA" => {
+                    in_data = true;
+                    b"354 Send the message\r\n"
+                }
+                "QUIT" => b"221 Bye\r\n",
+                _ => b"250 OK\r\n",
+            };
+
+            received.push(line.clone());
+            writer.write_all(reply).await.unwrap();
+
+            if line == "QUIT" {
+                break;
+            }
+        }
+
+        received
+    }
+
+    #[tokio::test]
+    async fn test_smtp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let session = tokio::spawn(serve_session(listener));
+
+        SmtpNotifier::new(
+            "127.0.0.1",
+            Some(port),
+            None,
+            None,
+            true,
+            "sshn <sshn@example.org>",
+            "me@example.org",
+        )
+        .unwrap()
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let received = session.await.unwrap();
+
+        assert!(received.contains(&String::from("MAIL FROM:<sshn@example.org>")));
+        assert!(received.contains(&String::from("RCPT TO:<me@example.org>")));
+        assert!(received.contains(&format!("Subject: {}", Event::Test.title())));
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let result = SmtpNotifier::new("localhost", None, None, None, true, "sshn", "me");
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sshn_lib::publication::Publication;
+
+use crate::error::Result;
+
+mod chat;
+mod desktop;
+mod email;
+mod push;
+mod webhook;
+
+/// Amount of seconds sending a single notification may take.
+const TIMEOUT: u64 = 10;
+
+/// The HTTP client shared by the notifiers, with a timeout so an unresponsive service cannot stall watching.
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(TIMEOUT))
+        .build()
+        .unwrap_or_default()
+}
+
+/// Send a request of a notifier, leaving its url out of errors as it may contain a token.
+async fn send(request: reqwest::RequestBuilder) -> Result<()> {
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(reqwest::Error::without_url)?;
+
+    Ok(())
+}
+
+/// Something that happened which the user wants to know about.
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// A publication matching a watch rule appeared.
+    NewPublication {
+        rule: String,
+        publication: Publication,
+    },
+    /// A reply was sent automatically to a publication.
+    Replied {
+        rule: String,
+        id: String,
+        name: String,
+        replied: bool,
+        error: Option<String>,
+    },
+    /// Checking a watch rule failed, e.g. because the session expired.
+    RuleFailed { rule: String, error: String },
+    /// Sent by `notify test`, to check whether the notifiers are configured correctly.
+    Test,
+}
+
+impl Event {
+    /// The fields of all events, as the columns of a CSV stream of events.
+    pub const COLUMNS: &'static [&'static str] = &[
+        "event",
+        "rule",
+        "id",
+        "name",
+        "replied",
+        "error",
+        "publication.id",
+        "publication.name",
+        "publication.city",
+        "publication.nr_of_applicants",
+        "publication.nr_of_people_with_higher_priority",
+        "publication.is_match",
+        "publication.rent",
+    ];
+
+    pub fn title(&self) -> String {
+        match self {
+            Event::NewPublication { publication, .. } => {
+                format!("New room in {}", publication.city())
+            }
+            Event::Replied { replied: true, .. } => String::from("Replied to room"),
+            Event::Replied { replied: false, .. } => String::from("Failed to reply to room"),
+            Event::RuleFailed { .. } => String::from("Failed to check for rooms"),
+            Event::Test => String::from("SSHN test notification"),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Event::NewPublication { rule, publication } => format!(
+                "{} for €{:.2}, {} applicants ({}). ID: {}",
+                publication.name(),
+                publication.rent(),
+                publication.nr_of_applicants(),
+                rule,
+                publication.id()
+            ),
+            Event::Replied {
+                rule,
+                id,
+                name,
+                error,
+                ..
+            } => match error {
+                Some(error) => format!("{} ({}), ID: {}: {}", name, rule, id, error),
+                None => format!("{} ({}), ID: {}", name, rule, id),
+            },
+            Event::RuleFailed { rule, error } => format!("{}: {}", rule, error),
+            Event::Test => String::from("Notifications from sshn-cli are working."),
+        }
+    }
+}
+
+/// Somewhere notifications can be sent to.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, event: &Event) -> Result<()>;
+}
+
+/// The configuration of a notifier.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Sink {
+    /// A desktop notification, shown using libnotify's `notify-send`.
+    Desktop,
+    /// POST the event as JSON to a url.
+    Webhook { url: String },
+    /// Publish to an ntfy topic, e.g. `https://ntfy.sh/my-topic`.
+    Ntfy { url: String, token: Option<String> },
+    /// Push a message to a Gotify server.
+    Gotify {
+        url: String,
+        token: String,
+        priority: Option<u8>,
+    },
+    /// Send an email using an SMTP server.
+    Smtp {
+        host: String,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+        /// Connect without TLS, only meant for local mail servers.
+        #[serde(default)]
+        insecure: bool,
+        from: String,
+        to: String,
+    },
+    /// Send a message to a chat using a Telegram bot.
+    Telegram {
+        token: String,
+        chat_id: String,
+        /// The Bot API server to use, e.g. a self-hosted one [default: https://api.telegram.org]
+        api_url: Option<String>,
+    },
+    /// Send a message to a Discord channel using a webhook.
+    Discord { url: String },
+    /// Send a message to a Matrix room.
+    Matrix {
+        homeserver: String,
+        room_id: String,
+        access_token: String,
+    },
+}
+
+/// Shown instead of secrets when the configuration is printed.
+const REDACTED: &str = "<redacted>";
+
+impl Sink {
+    /// A copy without tokens and passwords, so it can be shown.
+    pub fn redacted(&self) -> Sink {
+        let redacted = || String::from(REDACTED);
+
+        match self.clone() {
+            Sink::Ntfy { url, token } => Sink::Ntfy {
+                url,
+                token: token.map(|_| redacted()),
+            },
+            Sink::Gotify { url, priority, .. } => Sink::Gotify {
+                url,
+                token: redacted(),
+                priority,
+            },
+            Sink::Smtp {
+                host,
+                port,
+                username,
+                password,
+                insecure,
+                from,
+                to,
+            } => Sink::Smtp {
+                host,
+                port,
+                username,
+                password: password.map(|_| redacted()),
+                insecure,
+                from,
+                to,
+            },
+            Sink::Telegram {
+                chat_id, api_url, ..
+            } => Sink::Telegram {
+                token: redacted(),
+                chat_id,
+                api_url,
+            },
+            // The url of a Discord webhook contains its token.
+            Sink::Discord { .. } => Sink::Discord { url: redacted() },
+            Sink::Matrix {
+                homeserver,
+                room_id,
+                ..
+            } => Sink::Matrix {
+                homeserver,
+                room_id,
+                access_token: redacted(),
+            },
+            sink => sink,
+        }
+    }
+
+    /// Create the notifier, sending its requests using the given HTTP client.
+    pub fn notifier(&self, http_client: &reqwest::Client) -> Result<Box<dyn Notifier>> {
+        let http_client = http_client.clone();
+
+        let notifier: Box<dyn Notifier> = match self.clone() {
+            Sink::Desktop => Box::new(desktop::DesktopNotifier::default()),
+            Sink::Webhook { url } => Box::new(webhook::WebhookNotifier::new(url, http_client)),
+            Sink::Ntfy { url, token } => Box::new(push::NtfyNotifier::new(url, token, http_client)),
+            Sink::Gotify {
+                url,
+                token,
+                priority,
+            } => Box::new(push::GotifyNotifier::new(url, token, priority, http_client)),
+            Sink::Smtp {
+                host,
+                port,
+                username,
+                password,
+                insecure,
+                from,
+                to,
+            } => Box::new(email::SmtpNotifier::new(
+                &host, port, username, password, insecure, &from, &to,
+            )?),
+            Sink::Telegram {
+                token,
+                chat_id,
+                api_url,
+            } => Box::new(chat::TelegramNotifier::new(
+                token,
+                chat_id,
+                api_url,
+                http_client,
+            )),
+            Sink::Discord { url } => Box::new(chat::DiscordNotifier::new(url, http_client)),
+            Sink::Matrix {
+                homeserver,
+                room_id,
+                access_token,
+            } => Box::new(chat::MatrixNotifier::new(
+                homeserver,
+                room_id,
+                access_token,
+                http_client,
+            )),
+        };
+
+        Ok(notifier)
+    }
+}
+
+/// All configured notifiers.
+pub struct Notifiers {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new(sinks: &[Sink]) -> Result<Self> {
+        let http_client = http_client();
+
+        let notifiers = sinks
+            .iter()
+            .map(|sink| sink.notifier(&http_client))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { notifiers })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    /// Send the event to every notifier, returns the errors of the notifiers that failed.
+    pub async fn send(&self, event: &Event) -> Vec<crate::error::Error> {
+        let mut errors = Vec::new();
+
+        for notifier in self.notifiers.iter() {
+            if let Err(error) = notifier.notify(event).await {
+                log::error!("Failed to send notification: {}", error);
+
+                errors.push(error);
+            }
+        }
+
+        errors
+    }
+}
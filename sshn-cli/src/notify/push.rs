@@ -0,0 +1,136 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::error::Result;
+
+use super::{send, Event, Notifier};
+
+/// Publishes events to an ntfy topic.
+pub struct NtfyNotifier {
+    url: String,
+    token: Option<String>,
+    http_client: reqwest::Client,
+}
+
+impl NtfyNotifier {
+    pub fn new(url: String, token: Option<String>, http_client: reqwest::Client) -> Self {
+        Self {
+            url,
+            token,
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let mut request = self
+            .http_client
+            .post(&self.url)
+            .header("Title", event.title())
+            .body(event.message());
+
+        if let Some(token) = self.token.as_ref() {
+            request = request.bearer_auth(token);
+        }
+
+        send(request).await
+    }
+}
+
+/// Pushes events to a Gotify server.
+pub struct GotifyNotifier {
+    url: String,
+    token: String,
+    priority: Option<u8>,
+    http_client: reqwest::Client,
+}
+
+impl GotifyNotifier {
+    pub fn new(
+        url: String,
+        token: String,
+        priority: Option<u8>,
+        http_client: reqwest::Client,
+    ) -> Self {
+        Self {
+            url,
+            token,
+            priority,
+            http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let url = format!("{}/message", self.url.trim_end_matches('/'));
+
+        let request = self
+            .http_client
+            .post(url)
+            .header("X-Gotify-Key", &self.token)
+            .json(&json!({
+                "title": event.title(),
+                "message": event.message(),
+                "priority": self.priority.unwrap_or(5),
+            }));
+
+        send(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use sshn_lib::test_support::MockServer;
+
+    use super::*;
+    use crate::notify::http_client;
+
+    #[tokio::test]
+    async fn test_ntfy() {
+        let server = MockServer::start().await.unwrap();
+
+        NtfyNotifier::new(
+            format!("{}/rooms", server.url()),
+            Some(String::from("secret")),
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+
+        assert_eq!(request.path, "/rooms");
+        assert_eq!(request.header("title"), Some("SSHN test notification"));
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert_eq!(request.body, Event::Test.message());
+    }
+
+    #[tokio::test]
+    async fn test_gotify() {
+        let server = MockServer::start().await.unwrap();
+
+        GotifyNotifier::new(
+            format!("{}/", server.url()),
+            String::from("secret"),
+            None,
+            http_client(),
+        )
+        .notify(&Event::Test)
+        .await
+        .unwrap();
+
+        let request = &server.requests()[0];
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+
+        assert_eq!(request.path, "/message");
+        assert_eq!(request.header("x-gotify-key"), Some("secret"));
+        assert_eq!(body["title"], Event::Test.title());
+        assert_eq!(body["priority"], 5);
+    }
+}
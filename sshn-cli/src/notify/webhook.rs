@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+
+use crate::error::Result;
+
+use super::{send, Event, Notifier};
+
+/// Posts every event as JSON to a url.
+pub struct WebhookNotifier {
+    url: String,
+    http_client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, http_client: reqwest::Client) -> Self {
+        Self { url, http_client }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &Event) -> Result<()> {
+        let request = self.http_client.post(&self.url).json(event);
+
+        send(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sshn_lib::test_support::MockServer;
+
+    use super::*;
+    use crate::notify::http_client;
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let server = MockServer::start().await.unwrap();
+
+        WebhookNotifier::new(format!("{}/hook", server.url()), http_client())
+            .notify(&Event::Test)
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hook");
+        assert_eq!(request.body, r#"{"event":"test"}"#);
+    }
+}
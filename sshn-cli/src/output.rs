@@ -0,0 +1,329 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sshn_lib::{publication::Publication, ranking::RankedPublication};
+
+use crate::{commands::ProfileSummary, error::Result};
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Human readable tables and messages.
+    #[default]
+    Table,
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    Csv,
+    Yaml,
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Select the format results are printed in. Should be called once, before anything is printed.
+pub fn select(format: OutputFormat) {
+    if FORMAT.set(format).is_err() {
+        log::warn!("Output format was already selected");
+    }
+}
+
+pub fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Whether results are printed for other programs, in which case messages should not end up on stdout.
+pub fn is_machine_readable() -> bool {
+    format() != OutputFormat::Table
+}
+
+/// A record that can be shown as a row in a table.
+pub trait Tabular: Serialize {
+    fn headers() -> Vec<&'static str>;
+
+    fn row(&self) -> Vec<String>;
+}
+
+impl Tabular for Publication {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Can reply?",
+            "Name",
+            "City",
+            "Number of applicants",
+            "Gross rent",
+            "ID",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let is_match = if self.is_match() { "Yes" } else { "No" };
+
+        vec![
+            is_match.to_string(),
+            self.name().to_string(),
+            self.city().to_string(),
+            self.nr_of_applicants().to_string(),
+            self.rent().to_string(),
+            self.id().to_string(),
+        ]
+    }
+}
+
+impl Tabular for RankedPublication {
+    fn headers() -> Vec<&'static str> {
+        let mut headers = vec!["Score"];
+
+        headers.extend(Publication::headers());
+
+        headers
+    }
+
+    fn row(&self) -> Vec<String> {
+        let mut row = vec![format!("{:.2}", self.score())];
+
+        row.extend(self.publication().row());
+
+        row
+    }
+}
+
+impl Tabular for ProfileSummary {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Selected"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let selected = if self.selected { "Yes" } else { "No" };
+
+        vec![self.name.clone(), selected.to_string()]
+    }
+}
+
+/// Print a list of records in the selected format.
+pub fn print_list<T: Tabular>(records: &[T]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    match format() {
+        OutputFormat::Table => {
+            let mut table = Table::new();
+
+            table.add_row(Row::new(T::headers().into_iter().map(Cell::new).collect()));
+
+            for record in records {
+                table.add_row(Row::new(
+                    record.row().iter().map(|cell| Cell::new(cell)).collect(),
+                ));
+            }
+
+            table.print(&mut stdout)?;
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, records)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut stdout, record)?;
+                writeln!(stdout)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout);
+
+            for record in records {
+                writer.serialize(record)?;
+            }
+
+            writer.flush()?;
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_writer(&mut stdout, records)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a single record in the selected format. Should only be used for machine readable formats,
+/// as single records are shown as messages otherwise.
+pub fn print_record<T: Serialize>(record: &T) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    match format() {
+        OutputFormat::Table | OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, record)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut stdout, record)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Csv => {
+            let (headers, values): (Vec<String>, Vec<String>) =
+                flatten(record)?.into_iter().unzip();
+
+            let mut writer = csv::Writer::from_writer(stdout);
+
+            writer.write_record(&headers)?;
+            writer.write_record(&values)?;
+
+            writer.flush()?;
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_writer(&mut stdout, record)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints records one at a time as they happen, e.g. the events of `watch`.
+/// As CSV, the header with the given columns is only printed once,
+/// columns a record does not have are left empty.
+pub struct RecordStream {
+    columns: Vec<String>,
+    header_printed: AtomicBool,
+}
+
+impl RecordStream {
+    pub fn new<C: Into<String>, I: IntoIterator<Item = C>>(columns: I) -> Self {
+        Self {
+            columns: columns.into_iter().map(Into::into).collect(),
+            header_printed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn print<T: Serialize>(&self, record: &T) -> Result<()> {
+        if format() != OutputFormat::Csv {
+            return print_record(record);
+        }
+
+        let fields = flatten(record)?;
+
+        let row: Vec<&str> = self
+            .columns
+            .iter()
+            .map(|column| {
+                fields
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .map_or("", |(_, value)| value.as_str())
+            })
+            .collect();
+
+        let mut writer = csv::Writer::from_writer(std::io::stdout().lock());
+
+        if !self.header_printed.swap(true, Ordering::SeqCst) {
+            writer.write_record(&self.columns)?;
+        }
+
+        writer.write_record(&row)?;
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Flatten a record into named columns, as CSV has no nesting.
+/// Nested fields are named by their path, e.g. `publication.city` or `watch.0.name`.
+fn flatten<T: Serialize>(record: &T) -> Result<Vec<(String, String)>> {
+    let mut columns = Vec::new();
+
+    flatten_value(String::new(), serde_json::to_value(record)?, &mut columns);
+
+    Ok(columns)
+}
+
+fn flatten_value(path: String, value: Value, columns: &mut Vec<(String, String)>) {
+    let join = |key: &str| match path.is_empty() {
+        true => key.to_string(),
+        false => format!("{}.{}", path, key),
+    };
+
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                flatten_value(join(&key), value, columns);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.into_iter().enumerate() {
+                flatten_value(join(&index.to_string()), value, columns);
+            }
+        }
+        Value::Null => columns.push((path, String::new())),
+        Value::String(value) => columns.push((path, value)),
+        value => columns.push((path, value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sshn_lib::test_support::PublicationBuilder;
+
+    use super::*;
+    use crate::notify::Event;
+
+    #[test]
+    fn test_flatten() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+            [filters]
+            cities = ["Nijmegen", "Arnhem"]
+
+            [[watch]]
+            name = "cheap"
+            "#,
+        )
+        .unwrap();
+
+        let columns = flatten(&config).unwrap();
+
+        let column = |name: &str| {
+            columns
+                .iter()
+                .find(|(column, _)| column == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(column("filters.cities.1"), Some("Arnhem"));
+        assert_eq!(column("watch.0.name"), Some("cheap"));
+        assert_eq!(column("profile"), Some(""));
+    }
+
+    #[test]
+    fn test_event_columns() {
+        let publication = PublicationBuilder::new("1").build();
+
+        let events = [
+            Event::NewPublication {
+                rule: String::from("cheap"),
+                publication,
+            },
+            Event::Replied {
+                rule: String::from("cheap"),
+                id: String::from("1"),
+                name: String::from("Room"),
+                replied: false,
+                error: Some(String::from("Too late")),
+            },
+            Event::Test,
+        ];
+
+        // Every field of every event must have a column in the stream of `watch`.
+        for event in events {
+            for (column, _) in flatten(&event).unwrap() {
+                assert!(Event::COLUMNS.contains(&column.as_str()), "{}", column);
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use std::{fs, path::PathBuf, sync::OnceLock, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::AuthOptions,
+    error::{Error, Result},
+    WebDriver,
+};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+static CURRENT: OnceLock<String> = OnceLock::new();
+
+/// Select the profile used by this invocation. Should be called once, before any secrets are accessed.
+/// Unless `allow_missing` is set, returns an error if the profile does not exist,
+/// so a typo does not silently create a new profile.
+pub fn select<N: Into<String>>(name: N, allow_missing: bool) -> Result<()> {
+    let name = name.into();
+
+    if !is_valid_name(&name) {
+        return Err(Error::InvalidProfileName(name));
+    }
+
+    if !allow_missing && name != DEFAULT_PROFILE && Profiles::load()?.get(&name).is_none() {
+        return Err(Error::UnknownProfile(name));
+    }
+
+    if CURRENT.set(name).is_err() {
+        log::warn!("Profile was already selected");
+    }
+
+    Ok(())
+}
+
+/// The name of the profile used by this invocation.
+pub fn current() -> &'static str {
+    CURRENT.get().map(String::as_str).unwrap_or(DEFAULT_PROFILE)
+}
+
+/// Names are restricted so every profile maps to its own environment variables in the env secret store,
+/// which are uppercased and use '_' instead of '-'.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Settings that are remembered per profile, so a new login can be done without passing them again.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub webdriver: Option<WebDriver>,
+    pub webdriver_port: Option<u16>,
+    pub auto_start_webdriver: Option<bool>,
+    pub login_url: Option<String>,
+    pub webdriver_url: Option<String>,
+    pub webdriver_path: Option<PathBuf>,
+    pub headless: Option<bool>,
+    pub user_agent: Option<String>,
+    pub proxy: Option<String>,
+    pub webdriver_timeout: Option<u64>,
+    pub login_timeout: Option<u64>,
+    pub debug_dir: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Fill the settings that are not set with the settings of the other.
+    pub fn or(self, other: Settings) -> Settings {
+        Settings {
+            webdriver: self.webdriver.or(other.webdriver),
+            webdriver_port: self.webdriver_port.or(other.webdriver_port),
+            auto_start_webdriver: self.auto_start_webdriver.or(other.auto_start_webdriver),
+            login_url: self.login_url.or(other.login_url),
+            webdriver_url: self.webdriver_url.or(other.webdriver_url),
+            webdriver_path: self.webdriver_path.or(other.webdriver_path),
+            headless: self.headless.or(other.headless),
+            user_agent: self.user_agent.or(other.user_agent),
+            proxy: self.proxy.or(other.proxy),
+            webdriver_timeout: self.webdriver_timeout.or(other.webdriver_timeout),
+            login_timeout: self.login_timeout.or(other.login_timeout),
+            debug_dir: self.debug_dir.or(other.debug_dir),
+        }
+    }
+
+    pub fn auth_options(&self) -> AuthOptions {
+        let mut options = AuthOptions::default();
+
+        if let Some(webdriver) = self.webdriver.clone() {
+            options = options.webdriver(webdriver);
+        }
+
+        if let Some(webdriver_port) = self.webdriver_port {
+            options = options.webdriver_port(webdriver_port);
+        }
+
+        if let Some(auto_start_webdriver) = self.auto_start_webdriver {
+            options = options.auto_start_webdriver(auto_start_webdriver);
+        }
+
+        if let Some(login_url) = self.login_url.as_ref() {
+            options = options.login_base_url(login_url);
+        }
+
+        if let Some(webdriver_url) = self.webdriver_url.as_ref() {
+            options = options.webdriver_url(webdriver_url);
+        }
+
+        if let Some(webdriver_path) = self.webdriver_path.as_ref() {
+            options = options.webdriver_path(webdriver_path);
+        }
+
+        if let Some(headless) = self.headless {
+            options = options.headless(headless);
+        }
+
+        if let Some(user_agent) = self.user_agent.as_ref() {
+            options = options.user_agent(user_agent);
+        }
+
+        if let Some(proxy) = self.proxy.as_ref() {
+            options = options.proxy(proxy);
+        }
+
+        if let Some(webdriver_timeout) = self.webdriver_timeout {
+            options = options.webdriver_timeout(Duration::from_secs(webdriver_timeout));
+        }
+
+        if let Some(login_timeout) = self.login_timeout {
+            options = options.login_timeout(Duration::from_secs(login_timeout));
+        }
+
+        if let Some(debug_dir) = self.debug_dir.as_ref() {
+            options = options.debug_dir(debug_dir);
+        }
+
+        options
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Profile {
+    name: String,
+    #[serde(default)]
+    settings: Settings,
+}
+
+impl Profile {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            settings: Settings::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+}
+
+/// The list of known profiles, stored in the data directory of the user.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    fn path() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir().ok_or(Error::MissingDataDir)?;
+
+        Ok(data_dir.join("sshn").join("profiles.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+
+        let mut profiles: Profiles = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(path)?)?
+        } else {
+            Profiles::default()
+        };
+
+        if profiles.get(DEFAULT_PROFILE).is_none() {
+            profiles.profiles.insert(0, Profile::new(DEFAULT_PROFILE));
+        }
+
+        Ok(profiles)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.iter()
+    }
+
+    pub fn get<N: AsRef<str>>(&self, name: N) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == name.as_ref())
+    }
+
+    /// Add a new profile, returns an error if it already exists.
+    pub fn add<N: Into<String>>(&mut self, name: N) -> Result<&mut Profile> {
+        let name = name.into();
+
+        if !is_valid_name(&name) {
+            return Err(Error::InvalidProfileName(name));
+        }
+
+        if self.get(&name).is_some() {
+            return Err(Error::ProfileExists(name));
+        }
+
+        self.profiles.push(Profile::new(name));
+
+        Ok(self.profiles.last_mut().unwrap())
+    }
+
+    /// Get the profile with the given name, creating it if it does not exist yet.
+    pub fn get_or_add<N: AsRef<str>>(&mut self, name: N) -> Result<&mut Profile> {
+        match self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name.as_ref())
+        {
+            Some(index) => Ok(&mut self.profiles[index]),
+            None => self.add(name.as_ref()),
+        }
+    }
+
+    pub fn remove<N: AsRef<str>>(&mut self, name: N) -> Result<Profile> {
+        let name = name.as_ref();
+
+        if name == DEFAULT_PROFILE {
+            return Err(Error::RemoveDefaultProfile);
+        }
+
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| Error::UnknownProfile(name.to_string()))?;
+
+        Ok(self.profiles.remove(index))
+    }
+
+    /// The settings of the currently selected profile.
+    pub fn current_settings() -> Settings {
+        match Self::load() {
+            Ok(profiles) => profiles
+                .get(current())
+                .map(|profile| profile.settings().clone())
+                .unwrap_or_default(),
+            Err(error) => {
+                log::warn!("Failed to load profiles: {}", error);
+
+                Settings::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_name("work-2"));
+
+        // These would share environment variables with `foo-x`.
+        assert!(!is_valid_name("foo_x"));
+        assert!(!is_valid_name("Foo-X"));
+
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("a b"));
+    }
+}
@@ -0,0 +1,54 @@
+use super::{memory::MemoryStore, SecretStore};
+use crate::error::Result;
+
+const ENV_PREFIX: &str = "SSHN_SECRET_";
+
+/// Reads secrets from environment variables, e.g. `SSHN_SECRET_TOKENS` for the `tokens` secret.
+///
+/// Environment variables cannot be changed for the parent process,
+/// so any secrets that are stored are only kept in memory.
+#[derive(Debug, Default)]
+pub struct EnvStore {
+    overrides: MemoryStore,
+    deleted: std::sync::Mutex<Vec<String>>,
+}
+
+impl EnvStore {
+    fn variable_name(identifier: &str) -> String {
+        format!(
+            "{}{}",
+            ENV_PREFIX,
+            identifier.to_uppercase().replace('-', "_")
+        )
+    }
+}
+
+impl SecretStore for EnvStore {
+    fn get(&self, identifier: &str) -> Result<Option<String>> {
+        if let Some(data) = self.overrides.get(identifier)? {
+            return Ok(Some(data));
+        }
+
+        if self
+            .deleted
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|id| id == identifier)
+        {
+            return Ok(None);
+        }
+
+        Ok(std::env::var(Self::variable_name(identifier)).ok())
+    }
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()> {
+        self.overrides.set(identifier, data)
+    }
+
+    fn delete(&self, identifier: &str) -> Result<()> {
+        self.deleted.lock().unwrap().push(identifier.to_string());
+
+        self.overrides.delete(identifier)
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+
+use super::SecretStore;
+use crate::error::{Error, Result};
+
+/// Name of the environment variable containing the passphrase used to encrypt the file.
+pub const PASSPHRASE_ENV: &str = "SSHN_SECRETS_PASSPHRASE";
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+/// Stores secrets in a file, encrypted using a passphrase taken from the environment.
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    passphrase: String,
+}
+
+impl FileStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let passphrase = std::env::var(PASSPHRASE_ENV).map_err(|_| Error::MissingPassphrase)?;
+
+        Ok(Self {
+            path: path.into(),
+            passphrase,
+        })
+    }
+
+    /// The default location of the secrets file, in the data directory of the user.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("sshn").join("secrets.enc"))
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<Key> {
+        let mut key = Key::default();
+
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, &mut key)
+            .map_err(|_| Error::Encryption)?;
+
+        Ok(key)
+    }
+
+    fn read(&self) -> Result<HashMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read(&self.path)?;
+
+        if contents.len() < SALT_LENGTH + NONCE_LENGTH {
+            return Err(Error::Decryption);
+        }
+
+        let (salt, rest) = contents.split_at(SALT_LENGTH);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+
+        let cipher = ChaCha20Poly1305::new(&self.derive_key(salt)?);
+
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Decryption)?;
+
+        let secrets = serde_json::from_slice(&plaintext)?;
+
+        Ok(secrets)
+    }
+
+    fn write(&self, secrets: &HashMap<String, String>) -> Result<()> {
+        let plaintext = serde_json::to_vec(secrets)?;
+
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let cipher = ChaCha20Poly1305::new(&self.derive_key(&salt)?);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| Error::Encryption)?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = [salt.as_slice(), nonce.as_slice(), &ciphertext].concat();
+
+        write_private(&self.path, &contents)?;
+
+        Ok(())
+    }
+}
+
+/// Write the file so that only the current user is able to read it.
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    std::io::Write::write_all(&mut options.open(path)?, contents)?;
+
+    Ok(())
+}
+
+impl SecretStore for FileStore {
+    fn get(&self, identifier: &str) -> Result<Option<String>> {
+        let mut secrets = self.read()?;
+
+        Ok(secrets.remove(identifier))
+    }
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()> {
+        let mut secrets = self.read()?;
+
+        secrets.insert(identifier.to_string(), data.to_string());
+
+        self.write(&secrets)
+    }
+
+    fn delete(&self, identifier: &str) -> Result<()> {
+        let mut secrets = self.read()?;
+
+        if secrets.remove(identifier).is_some() {
+            self.write(&secrets)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let path = std::env::temp_dir().join(format!("sshn-secrets-{}.enc", std::process::id()));
+
+        let store = FileStore {
+            path: path.clone(),
+            passphrase: String::from("correct horse battery staple"),
+        };
+
+        store.set("tokens", "secret").unwrap();
+
+        assert_eq!(store.get("tokens").unwrap().as_deref(), Some("secret"));
+        assert!(!fs::read(&path).unwrap().windows(6).any(|w| w == b"secret"));
+
+        let wrong_store = FileStore {
+            path: path.clone(),
+            passphrase: String::from("wrong"),
+        };
+
+        assert!(matches!(wrong_store.get("tokens"), Err(Error::Decryption)));
+
+        store.delete("tokens").unwrap();
+
+        assert_eq!(store.get("tokens").unwrap(), None);
+
+        fs::remove_file(path).unwrap();
+    }
+}
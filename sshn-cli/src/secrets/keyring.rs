@@ -0,0 +1,45 @@
+use keyring::Entry;
+
+use super::SecretStore;
+use crate::error::Result;
+
+const SERVICE_NAME: &str = "SSHN-cli";
+
+/// Stores secrets in the keyring of the operating system.
+#[derive(Debug, Default)]
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(identifier: &str) -> Result<Entry> {
+        let user = whoami::username();
+
+        let entry_name = format!("{}-{}", identifier, SERVICE_NAME);
+
+        let entry = Entry::new(&entry_name, &user)?;
+
+        Ok(entry)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, identifier: &str) -> Result<Option<String>> {
+        match Self::entry(identifier)?.get_password() {
+            Ok(data) => Ok(Some(data)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()> {
+        Self::entry(identifier)?.set_password(data)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, identifier: &str) -> Result<()> {
+        match Self::entry(identifier)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use super::SecretStore;
+use crate::error::Result;
+
+/// Keeps secrets in memory, so they are forgotten as soon as the program exits.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl SecretStore for MemoryStore {
+    fn get(&self, identifier: &str) -> Result<Option<String>> {
+        let secrets = self.secrets.lock().unwrap();
+
+        Ok(secrets.get(identifier).cloned())
+    }
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+
+        secrets.insert(identifier.to_string(), data.to_string());
+
+        Ok(())
+    }
+
+    fn delete(&self, identifier: &str) -> Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+
+        secrets.remove(identifier);
+
+        Ok(())
+    }
+}
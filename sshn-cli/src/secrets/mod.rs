@@ -0,0 +1,170 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sshn_lib::{AuthenticatedClient, Tokens};
+
+pub use crate::error::Result;
+use crate::{
+    auth, config,
+    error::Error,
+    profiles::{self, Profiles, DEFAULT_PROFILE},
+};
+
+mod env;
+mod file;
+mod keyring;
+mod memory;
+mod overlay;
+
+pub use self::{
+    env::EnvStore, file::FileStore, keyring::KeyringStore, memory::MemoryStore,
+    overlay::OverlayStore,
+};
+
+/// A place where secrets, such as tokens and credentials, can be stored.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, identifier: &str) -> Result<Option<String>>;
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()>;
+
+    fn delete(&self, identifier: &str) -> Result<()>;
+}
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretStoreKind {
+    /// The keyring of the operating system.
+    #[default]
+    Keyring,
+    /// A file encrypted with the passphrase in `SSHN_SECRETS_PASSPHRASE`.
+    File,
+    /// Environment variables named `SSHN_SECRET_<IDENTIFIER>`.
+    Env,
+    /// Memory, nothing is kept after the program exits.
+    Memory,
+}
+
+static STORE: OnceLock<Box<dyn SecretStore>> = OnceLock::new();
+
+/// Select the store used to keep secrets. Should be called once, before any secrets are accessed.
+/// A read only store reads the selected store, but keeps all changes in memory.
+pub fn init(kind: SecretStoreKind, path: Option<PathBuf>, read_only: bool) -> Result<()> {
+    let store: Box<dyn SecretStore> = match kind {
+        SecretStoreKind::Keyring => Box::new(KeyringStore),
+        SecretStoreKind::File => {
+            let path = path
+                .or_else(FileStore::default_path)
+                .ok_or(Error::MissingSecretsFile)?;
+
+            Box::new(FileStore::new(path)?)
+        }
+        SecretStoreKind::Env => Box::<EnvStore>::default(),
+        SecretStoreKind::Memory => Box::<MemoryStore>::default(),
+    };
+
+    let store: Box<dyn SecretStore> = if read_only {
+        Box::new(OverlayStore::new(store))
+    } else {
+        store
+    };
+
+    if STORE.set(store).is_err() {
+        log::warn!("Secret store was already initialized");
+    }
+
+    Ok(())
+}
+
+fn store() -> &'static dyn SecretStore {
+    STORE.get_or_init(|| Box::new(KeyringStore)).as_ref()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+/// The identifier of a secret belonging to the given profile.
+/// Secrets of the default profile are not prefixed, so they remain compatible with older versions.
+fn profile_identifier(profile: &str, identifier: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        identifier.to_string()
+    } else {
+        format!("{}-{}", profile, identifier)
+    }
+}
+
+pub fn set<I: AsRef<str>, T: Serialize>(identifier: I, data: &T) -> Result<()> {
+    let data = serde_json::to_string(data)?;
+
+    let identifier = profile_identifier(profiles::current(), identifier.as_ref());
+
+    store().set(&identifier, &data)
+}
+
+pub fn get<I: AsRef<str>, T: DeserializeOwned>(identifier: I) -> Result<T> {
+    let identifier = profile_identifier(profiles::current(), identifier.as_ref());
+
+    let data = store()
+        .get(&identifier)?
+        .ok_or(Error::MissingSecret(identifier))?;
+
+    let data = serde_json::from_str(&data)?;
+
+    Ok(data)
+}
+
+/// Remove the data stored under the given identifier, if there is any.
+pub fn delete<I: AsRef<str>>(identifier: I) -> Result<()> {
+    delete_for_profile(profiles::current(), identifier)
+}
+
+/// Remove the data stored under the given identifier for the given profile, if there is any.
+pub fn delete_for_profile<P: AsRef<str>, I: AsRef<str>>(profile: P, identifier: I) -> Result<()> {
+    store().delete(&profile_identifier(profile.as_ref(), identifier.as_ref()))
+}
+
+/// Store the given tokens, so they can be reused by the next invocation.
+pub fn store_tokens(tokens: &Tokens) {
+    if let Err(error) = set("tokens", tokens) {
+        log::warn!("Failed to store refreshed tokens: {}", error);
+    }
+}
+
+pub async fn get_client() -> Result<AuthenticatedClient> {
+    let tokens = get::<_, Tokens>("tokens").ok();
+
+    if let Some(tokens) = tokens.as_ref() {
+        if !tokens.refresh_token().has_expired() || !tokens.access_token().has_expired() {
+            return Ok(config::client()
+                .authenticate(tokens.clone())
+                .on_tokens_changed(store_tokens));
+        }
+    }
+
+    if let Ok(credentials) = get::<_, Credentials>("credentials") {
+        log::info!("Tokens expired, logging in using credentials");
+
+        let options = Profiles::current_settings()
+            .or(config::get().auth.clone())
+            .auth_options()
+            .remember_password(true);
+
+        return auth::headless_login(credentials.username, credentials.password, options).await;
+    }
+
+    match tokens {
+        Some(_) => Err(Error::SessionExpired),
+        None => Err(Error::MissingCredentials),
+    }
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use super::SecretStore;
+use crate::error::Result;
+
+/// Reads secrets from another store, but keeps all changes in memory, so the other store is never modified.
+/// Used while replaying recorded requests, whose tokens are redacted and must not replace the real ones.
+pub struct OverlayStore {
+    inner: Box<dyn SecretStore>,
+    /// Changed secrets, `None` if the secret was deleted.
+    changes: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl OverlayStore {
+    pub fn new(inner: Box<dyn SecretStore>) -> Self {
+        Self {
+            inner,
+            changes: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl SecretStore for OverlayStore {
+    fn get(&self, identifier: &str) -> Result<Option<String>> {
+        if let Some(change) = self.changes.lock().unwrap().get(identifier) {
+            return Ok(change.clone());
+        }
+
+        self.inner.get(identifier)
+    }
+
+    fn set(&self, identifier: &str, data: &str) -> Result<()> {
+        let mut changes = self.changes.lock().unwrap();
+
+        changes.insert(identifier.to_string(), Some(data.to_string()));
+
+        Ok(())
+    }
+
+    fn delete(&self, identifier: &str) -> Result<()> {
+        let mut changes = self.changes.lock().unwrap();
+
+        changes.insert(identifier.to_string(), None);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::MemoryStore;
+
+    #[test]
+    fn test_changes_do_not_reach_inner_store() {
+        let inner = MemoryStore::default();
+        inner.set("tokens", "real").unwrap();
+        inner.set("credentials", "real").unwrap();
+
+        let store = OverlayStore::new(Box::new(inner));
+
+        store.set("tokens", "REDACTED").unwrap();
+        store.delete("credentials").unwrap();
+
+        assert_eq!(store.get("tokens").unwrap().as_deref(), Some("REDACTED"));
+        assert_eq!(store.get("credentials").unwrap(), None);
+        assert_eq!(store.inner.get("tokens").unwrap().as_deref(), Some("real"));
+        assert_eq!(
+            store.inner.get("credentials").unwrap().as_deref(),
+            Some("real")
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    net::TcpListener,
+    path::PathBuf,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, Command},
+};
+
+use crate::{
+    error::{Error, Result},
+    WebDriver,
+};
+
+/// The amount of lines of the web driver's stderr that are kept for error messages.
+const MAX_STDERR_LINES: usize = 20;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A running web driver process, which is killed as soon as it is dropped.
+pub struct WebDriverProcess {
+    child: Child,
+    port: u16,
+    stderr: Arc<Mutex<Vec<String>>>,
+}
+
+impl WebDriverProcess {
+    /// Starts the given webdriver, then waits until said driver has started up.
+    /// If the given port is already in use, a free port is picked instead.
+    pub async fn start(
+        webdriver: WebDriver,
+        path: Option<PathBuf>,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let port = if is_port_free(port) {
+            port
+        } else {
+            let free_port = find_free_port()?;
+
+            log::warn!(
+                "Port {} is already in use, starting web driver on port {} instead",
+                port,
+                free_port
+            );
+
+            free_port
+        };
+
+        let mut command = match webdriver {
+            WebDriver::Chromium => {
+                let mut command = Command::new(path.unwrap_or(PathBuf::from("chromedriver")));
+
+                command.arg(format!("--port={}", port));
+
+                command
+            }
+            WebDriver::Gecko => {
+                let mut command = Command::new(path.unwrap_or(PathBuf::from("geckodriver")));
+
+                command.arg("--port").arg(port.to_string());
+
+                command
+            }
+        };
+
+        let mut child = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+
+        if let Some(pipe) = child.stderr.take() {
+            let stderr = Arc::clone(&stderr);
+
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(pipe).lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    log::debug!("Web driver: {}", line);
+
+                    let mut stderr = stderr.lock().unwrap();
+
+                    if stderr.len() >= MAX_STDERR_LINES {
+                        stderr.remove(0);
+                    }
+
+                    stderr.push(line);
+                }
+            });
+        }
+
+        let mut process = Self {
+            child,
+            port,
+            stderr,
+        };
+
+        process.wait_until_ready(timeout).await?;
+
+        Ok(process)
+    }
+
+    async fn wait_until_ready(&mut self, timeout: Duration) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            if tokio::net::TcpStream::connect(("127.0.0.1", self.port))
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+
+            if let Some(status) = self.child.try_wait()? {
+                return Err(Error::WebDriverStart(format!(
+                    "web driver exited with {}{}",
+                    status,
+                    self.stderr_message()
+                )));
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::WebDriverStart(format!(
+                    "web driver did not accept connections within {} seconds{}",
+                    timeout.as_secs_f32(),
+                    self.stderr_message()
+                )));
+            }
+
+            log::debug!("Could not connect to web driver yet, retrying...");
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    fn stderr_message(&self) -> String {
+        let stderr = self.stderr.lock().unwrap();
+
+        if stderr.is_empty() {
+            String::new()
+        } else {
+            format!(":\n{}", stderr.join("\n"))
+        }
+    }
+
+    /// The url that can be used to connect to the web driver.
+    pub fn url(&self) -> String {
+        format!("http://localhost:{}", self.port)
+    }
+
+    /// Stop the web driver and wait for it to exit.
+    pub async fn stop(mut self) -> Result<()> {
+        self.child.kill().await?;
+
+        Ok(())
+    }
+}
+
+impl Drop for WebDriverProcess {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            if let Err(error) = self.child.start_kill() {
+                log::warn!("Failed to kill web driver: {}", error);
+            }
+        }
+    }
+}
+
+fn is_port_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+fn find_free_port() -> Result<u16> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+
+    Ok(listener.local_addr()?.port())
+}
@@ -1 +1,2 @@
 pub mod publication;
+pub mod user;
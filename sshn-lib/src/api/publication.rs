@@ -5,7 +5,7 @@ use crate::{
     queries::get_publications_list,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Publication {
     id: String,
     name: String,
@@ -47,11 +47,25 @@ impl Publication {
 }
 
 pub fn convert_publications(data: get_publications_list::ResponseData) -> Result<Vec<Publication>> {
-    Ok(data
+    convert_publications_page(data).map(|(publications, _)| publications)
+}
+
+/// Convert a page of publications, together with the cursor of the next page if there is one.
+pub fn convert_publications_page(
+    data: get_publications_list::ResponseData,
+) -> Result<(Vec<Publication>, Option<String>)> {
+    let nodes = data
         .housing_publications
         .ok_or(Error::MissingPublications)?
         .nodes
-        .ok_or(Error::MissingPublications)?
+        .ok_or(Error::MissingPublications)?;
+
+    let next_cursor = nodes
+        .page_info
+        .has_next_page
+        .then_some(nodes.page_info.end_cursor);
+
+    let publications = nodes
         .edges
         .ok_or(Error::MissingPublications)?
         .into_iter()
@@ -84,5 +98,7 @@ pub fn convert_publications(data: get_publications_list::ResponseData) -> Result
                 rent,
             })
         })
-        .collect())
+        .collect();
+
+    Ok((publications, next_cursor))
 }
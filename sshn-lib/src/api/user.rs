@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    queries::get_logged_in_user,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct User {
+    email: Option<String>,
+    first_name: Option<String>,
+    prefixes: Option<String>,
+    last_name: Option<String>,
+}
+
+impl User {
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn first_name(&self) -> Option<&str> {
+        self.first_name.as_deref()
+    }
+
+    pub fn prefixes(&self) -> Option<&str> {
+        self.prefixes.as_deref()
+    }
+
+    pub fn last_name(&self) -> Option<&str> {
+        self.last_name.as_deref()
+    }
+
+    /// The full name of the user, including any prefixes to their last name.
+    pub fn name(&self) -> String {
+        [self.first_name(), self.prefixes(), self.last_name()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub fn convert_user(data: get_logged_in_user::ResponseData) -> Result<User> {
+    let user = data
+        .housing_logged_in_user
+        .ok_or(Error::MissingUser)?
+        .user
+        .ok_or(Error::MissingUser)?;
+
+    Ok(User {
+        email: user.email,
+        first_name: user.first_name,
+        prefixes: user.prefixes,
+        last_name: user.last_name,
+    })
+}
@@ -0,0 +1,196 @@
+//! Synchronous clients for the SSHN API, for use outside of an async runtime.
+//!
+//! Every client runs the requests on its own single threaded runtime, so they must not be used from within
+//! an async context, as blocking on a runtime inside another runtime panics.
+
+use std::sync::Arc;
+
+use graphql_client::GraphQLQuery;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    client::{self, Client as _, GraphqlResponse, LoginType},
+    error::{Error, Result},
+    publication::Publication,
+    queries::get_identity_config,
+    tokens::Tokens,
+    user::User,
+};
+
+fn runtime() -> Result<Arc<Runtime>> {
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)?;
+
+    Ok(Arc::new(runtime))
+}
+
+pub trait Client {
+    fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>>;
+}
+
+/// A blocking mirror of [`crate::UnAuthenticatedClient`].
+#[derive(Clone)]
+pub struct UnAuthenticatedClient {
+    inner: client::UnAuthenticatedClient,
+    runtime: Arc<Runtime>,
+}
+
+impl UnAuthenticatedClient {
+    pub fn new(graphql_url: Option<String>) -> Result<Self> {
+        Self::from_async(client::UnAuthenticatedClient::new(graphql_url))
+    }
+
+    /// Wrap an async client, so it can be configured using its builder methods.
+    pub fn from_async(inner: client::UnAuthenticatedClient) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: runtime()?,
+        })
+    }
+
+    pub fn auth(&self, login_type: LoginType) -> Result<Tokens> {
+        self.runtime.block_on(self.inner.auth(login_type))
+    }
+
+    /// End the session belonging to the given refresh token, revoking all of its tokens.
+    pub fn logout<T: AsRef<str>>(&self, refresh_token: T) -> Result<()> {
+        self.runtime.block_on(self.inner.logout(refresh_token))
+    }
+
+    /// Use previously obtained tokens to authenticate, without logging in again.
+    pub fn authenticate(self, tokens: Tokens) -> AuthenticatedClient {
+        AuthenticatedClient {
+            inner: self.inner.authenticate(tokens),
+            runtime: self.runtime,
+        }
+    }
+
+    pub fn login(self, login_type: LoginType) -> Result<AuthenticatedClient> {
+        let inner = self.runtime.block_on(self.inner.login(login_type))?;
+
+        Ok(AuthenticatedClient {
+            inner,
+            runtime: self.runtime,
+        })
+    }
+
+    /// Get the key set used by the identity provider to sign its tokens.
+    #[cfg(feature = "jwks")]
+    pub fn get_jwks(&self) -> Result<jsonwebtoken::jwk::JwkSet> {
+        self.runtime.block_on(self.inner.get_jwks())
+    }
+
+    /// Execute any GraphQL operation against the SSHN API, without authentication.
+    pub fn execute<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<GraphqlResponse<Q::ResponseData>> {
+        self.runtime.block_on(self.inner.execute::<Q>(variables))
+    }
+
+    pub fn get_endpoints(&self) -> Result<get_identity_config::ResponseData> {
+        self.runtime.block_on(self.inner.get_endpoints())
+    }
+}
+
+impl Client for UnAuthenticatedClient {
+    fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>> {
+        self.runtime.block_on(self.inner.get_publications_list(max))
+    }
+}
+
+/// A blocking mirror of [`crate::AuthenticatedClient`].
+#[derive(Clone)]
+pub struct AuthenticatedClient {
+    inner: client::AuthenticatedClient,
+    runtime: Arc<Runtime>,
+}
+
+impl AuthenticatedClient {
+    pub fn new(graphql_url: Option<String>, tokens: Tokens) -> Result<Self> {
+        Self::from_async(client::AuthenticatedClient::new(graphql_url, tokens))
+    }
+
+    /// Wrap an async client, so it can be configured using its builder methods.
+    pub fn from_async(inner: client::AuthenticatedClient) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: runtime()?,
+        })
+    }
+
+    /// End the current session, revoking the tokens of this client and all of its clones.
+    pub fn logout(self) -> Result<UnAuthenticatedClient> {
+        let inner = self.runtime.block_on(self.inner.logout())?;
+
+        Ok(UnAuthenticatedClient {
+            inner,
+            runtime: self.runtime,
+        })
+    }
+
+    /// Get new tokens using the current refresh token.
+    pub fn refresh(&self) -> Result<()> {
+        self.runtime.block_on(self.inner.refresh())
+    }
+
+    /// The current tokens, which change every time they are refreshed.
+    pub fn tokens(&self) -> Tokens {
+        self.inner.tokens()
+    }
+
+    /// Execute any GraphQL operation against the SSHN API, as the logged in user.
+    pub fn execute<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<GraphqlResponse<Q::ResponseData>> {
+        self.runtime.block_on(self.inner.execute::<Q>(variables))
+    }
+
+    /// Get the account details of the logged in user.
+    pub fn get_user(&self) -> Result<User> {
+        self.runtime.block_on(self.inner.get_user())
+    }
+
+    /// Reply to a publication, given that publications id.
+    pub fn reply_to_publication<I: Into<String>>(&self, publication_id: I) -> Result<()> {
+        self.runtime
+            .block_on(self.inner.reply_to_publication(publication_id))
+    }
+}
+
+impl Client for AuthenticatedClient {
+    fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>> {
+        self.runtime.block_on(self.inner.get_publications_list(max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn test_blocking_client() {
+        // The mock server keeps running on the worker threads of its own runtime.
+        let server_runtime = Runtime::new().unwrap();
+        let server = server_runtime.block_on(MockServer::start()).unwrap();
+
+        let client = UnAuthenticatedClient::from_async(server.client()).unwrap();
+
+        assert_eq!(client.get_publications_list(5).unwrap().len(), 2);
+
+        let client = client
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .unwrap();
+
+        client.reply_to_publication(PUBLICATION_ID).unwrap();
+    }
+}
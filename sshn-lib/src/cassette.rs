@@ -0,0 +1,387 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{Error, Result},
+    transport::Transport,
+};
+
+/// Keys of request and response bodies whose values are replaced before an interaction is stored.
+const REDACTED_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "session_state",
+    "code",
+    "code_verifier",
+    "username",
+    "password",
+];
+
+const REDACTED: &str = "REDACTED";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests to the server and store every request and response.
+    Record,
+    /// Answer requests with the stored responses, in the order they were recorded.
+    Replay,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    operation_name: Option<String>,
+    body: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+/// A directory of recorded HTTP interactions with the SSHN API, one JSON file per interaction.
+/// Secrets like tokens and passwords are redacted and headers are never stored,
+/// so recordings can be shared when reporting issues.
+pub struct Cassette {
+    dir: PathBuf,
+    mode: CassetteMode,
+    interactions: Vec<Interaction>,
+    position: Mutex<usize>,
+}
+
+impl Cassette {
+    /// Record interactions into the given directory, replacing earlier recordings in it.
+    /// Only files named like recorded interactions are removed, other files in the directory are kept.
+    pub fn record<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+
+        fs::create_dir_all(&dir)?;
+
+        for path in interaction_files(&dir)? {
+            fs::remove_file(path)?;
+        }
+
+        Ok(Self {
+            dir,
+            mode: CassetteMode::Record,
+            interactions: Vec::new(),
+            position: Mutex::new(0),
+        })
+    }
+
+    /// Replay the interactions recorded in the given directory.
+    pub fn replay<P: Into<PathBuf>>(dir: P) -> Result<Self> {
+        let dir = dir.into();
+
+        let interactions = interaction_files(&dir)?
+            .into_iter()
+            .map(|path| Ok(serde_json::from_str(&fs::read_to_string(path)?)?))
+            .collect::<Result<Vec<Interaction>>>()?;
+
+        Ok(Self {
+            dir,
+            mode: CassetteMode::Replay,
+            interactions,
+            position: Mutex::new(0),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) async fn send(
+        &self,
+        transport: &dyn Transport,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        let recorded_request = RecordedRequest::from_request(&request);
+
+        match self.mode {
+            CassetteMode::Record => {
+                let response = transport.execute(request).await?;
+
+                let status = response.status().as_u16();
+
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(String::from);
+
+                let body = response.bytes().await?;
+
+                let interaction = Interaction {
+                    request: recorded_request,
+                    response: RecordedResponse {
+                        status,
+                        content_type,
+                        body: redact(parse_body(&body)),
+                    },
+                };
+
+                let position = {
+                    let mut position = self.position.lock().unwrap();
+                    *position += 1;
+                    *position
+                };
+
+                let name = interaction
+                    .request
+                    .operation_name
+                    .clone()
+                    .unwrap_or_else(|| interaction.request.path.replace('/', "_"));
+
+                fs::write(
+                    self.dir.join(format!("{:04}-{}.json", position, name)),
+                    serde_json::to_string_pretty(&interaction)?,
+                )?;
+
+                // The original body is returned, so the client keeps working with unredacted tokens.
+                build_response(status, interaction.response.content_type, body.to_vec())
+            }
+            CassetteMode::Replay => {
+                let index = {
+                    let mut position = self.position.lock().unwrap();
+                    *position += 1;
+                    *position - 1
+                };
+
+                let interaction = self.interactions.get(index).ok_or_else(|| {
+                    Error::Cassette(format!(
+                        "no recorded response left for {} {}",
+                        recorded_request.method, recorded_request.path
+                    ))
+                })?;
+
+                let recorded = &interaction.request;
+
+                if recorded.method != recorded_request.method
+                    || recorded.path != recorded_request.path
+                    || recorded.operation_name != recorded_request.operation_name
+                {
+                    return Err(Error::Cassette(format!(
+                        "expected {} {} ({}), but got {} {} ({})",
+                        recorded.method,
+                        recorded.path,
+                        recorded.operation_name.as_deref().unwrap_or("-"),
+                        recorded_request.method,
+                        recorded_request.path,
+                        recorded_request.operation_name.as_deref().unwrap_or("-"),
+                    )));
+                }
+
+                let response = &interaction.response;
+
+                let body = match &response.body {
+                    Value::String(body)
+                        if !response
+                            .content_type
+                            .as_deref()
+                            .is_some_and(|content_type| content_type.contains("json")) =>
+                    {
+                        body.clone().into_bytes()
+                    }
+                    body => serde_json::to_vec(body)?,
+                };
+
+                build_response(response.status, response.content_type.clone(), body)
+            }
+        }
+    }
+}
+
+impl RecordedRequest {
+    fn from_request(request: &reqwest::Request) -> Self {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(parse_body)
+            .unwrap_or(Value::Null);
+
+        let operation_name = body
+            .get("operationName")
+            .and_then(Value::as_str)
+            .map(String::from);
+
+        Self {
+            method: request.method().to_string(),
+            path: request.url().path().to_string(),
+            operation_name,
+            body: redact(body),
+        }
+    }
+}
+
+/// Whether the file is named like a recorded interaction, e.g. `0001-GetPublicationsList.json`.
+fn is_interaction_file(path: &Path) -> bool {
+    let Some(stem) = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".json"))
+    else {
+        return false;
+    };
+
+    let Some((position, name)) = stem.split_once('-') else {
+        return false;
+    };
+
+    position.len() == 4 && position.bytes().all(|byte| byte.is_ascii_digit()) && !name.is_empty()
+}
+
+fn interaction_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && is_interaction_file(path))
+        .collect();
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Parse a body as JSON, or as form data, falling back to the raw text.
+fn parse_body(body: &[u8]) -> Value {
+    if body.is_empty() {
+        return Value::Null;
+    }
+
+    if let Ok(json) = serde_json::from_slice(body) {
+        return json;
+    }
+
+    let text = String::from_utf8_lossy(body).to_string();
+
+    match serde_urlencoded::from_str::<Vec<(String, String)>>(&text) {
+        Ok(form) if !form.is_empty() && text.contains('=') => Value::Object(
+            form.into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        _ => Value::String(text),
+    }
+}
+
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = if REDACTED_KEYS.contains(&key.as_str()) && !value.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(value)
+                    };
+
+                    (key, value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(redact).collect()),
+        value => value,
+    }
+}
+
+fn build_response(
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+) -> Result<reqwest::Response> {
+    let mut builder = http::Response::builder().status(status);
+
+    if let Some(content_type) = content_type {
+        builder = builder.header(reqwest::header::CONTENT_TYPE, content_type);
+    }
+
+    let response = builder
+        .body(body)
+        .map_err(|error| Error::Cassette(error.to_string()))?;
+
+    Ok(response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{Client, LoginType, UnAuthenticatedClient},
+        test_support::*,
+    };
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("sshn-cassette-{}", std::process::id()));
+
+        let server = MockServer::start().await.unwrap();
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("0001-_auth_token.json"), "{}").unwrap();
+        fs::write(dir.join("settings.json"), "{}").unwrap();
+
+        let client = server
+            .client()
+            .cassette(Cassette::record(&dir).unwrap())
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let recorded = client.get_publications_list(5).await.unwrap();
+
+        let token_interaction = fs::read_to_string(dir.join("0001-_auth_token.json")).unwrap();
+
+        assert!(token_interaction.contains(r#""password": "REDACTED""#));
+        assert!(!token_interaction.contains(client.tokens().access_token().content()));
+
+        // Files that are not recorded interactions are left alone, and are not replayed.
+        assert!(dir.join("settings.json").exists());
+
+        drop(server);
+
+        let client = UnAuthenticatedClient::new(Some(String::from("http://127.0.0.1:1/graphql")))
+            .auth_url("http://127.0.0.1:1/auth")
+            .cassette(Cassette::replay(&dir).unwrap())
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let replayed = client.get_publications_list(5).await.unwrap();
+
+        assert_eq!(replayed.len(), recorded.len());
+        assert_eq!(replayed[0].id(), recorded[0].id());
+
+        assert!(matches!(
+            client.get_publications_list(5).await,
+            Err(Error::Cassette(_))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RealmAccess {
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// The claims contained in the access and ID tokens handed out by the SSHN identity provider.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Claims {
+    sub: Option<String>,
+    email: Option<String>,
+    name: Option<String>,
+    preferred_username: Option<String>,
+    exp: Option<i64>,
+    #[serde(default)]
+    realm_access: RealmAccess,
+}
+
+impl Claims {
+    /// Decode the claims of a JWT, without verifying its signature.
+    pub fn decode<T: AsRef<str>>(token: T) -> Result<Self> {
+        let payload = token.as_ref().split('.').nth(1).ok_or(Error::InvalidJwt)?;
+
+        let payload = BASE64_URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|_| Error::InvalidJwt)?;
+
+        let claims = serde_json::from_slice(&payload)?;
+
+        Ok(claims)
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.sub.as_deref()
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.preferred_username.as_deref()
+    }
+
+    pub fn roles(&self) -> &[String] {
+        &self.realm_access.roles
+    }
+
+    /// The moment the token expires, according to its `exp` claim.
+    pub fn expires(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.exp?, 0)
+    }
+}
+
+#[cfg(feature = "jwks")]
+impl Claims {
+    /// Decode the claims of a JWT, verifying its signature against the given key set.
+    pub fn verify<T: AsRef<str>>(token: T, jwks: &jsonwebtoken::jwk::JwkSet) -> Result<Self> {
+        use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+
+        let header = decode_header(token.as_ref())?;
+
+        let kid = header.kid.ok_or(Error::InvalidJwt)?;
+
+        let jwk = jwks.find(&kid).ok_or(Error::InvalidJwt)?;
+
+        let key = DecodingKey::from_jwk(jwk)?;
+
+        let mut validation = Validation::new(header.alg);
+
+        // Keycloak sets the audience to the account client, not to the client that requested the token.
+        validation.validate_aud = false;
+
+        let data = decode::<Claims>(token.as_ref(), &key, &validation)?;
+
+        Ok(data.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_claims() {
+        let payload = r#"{"exp":1713462638,"sub":"cf4d60fd","email":"john@example.com","name":"John Doe","realm_access":{"roles":["portal-user"]}}"#;
+
+        let token = format!(
+            "{}.{}.signature",
+            BASE64_URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256"}"#),
+            BASE64_URL_SAFE_NO_PAD.encode(payload)
+        );
+
+        let claims = Claims::decode(token).unwrap();
+
+        assert_eq!(claims.subject(), Some("cf4d60fd"));
+        assert_eq!(claims.email(), Some("john@example.com"));
+        assert_eq!(claims.name(), Some("John Doe"));
+        assert_eq!(claims.roles(), ["portal-user"]);
+        assert_eq!(claims.expires().unwrap().timestamp(), 1713462638);
+    }
+
+    #[test]
+    fn test_decode_invalid_token() {
+        assert!(Claims::decode("not-a-jwt").is_err());
+    }
+}
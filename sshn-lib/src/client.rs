@@ -1,29 +1,140 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::{Duration as StdDuration, Instant},
+};
 
 use async_trait::async_trait;
-use graphql_client::GraphQLQuery;
+use chrono::Duration;
+use graphql_client::{GraphQLQuery, QueryBody};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex as AsyncMutex;
 
 use crate::{
-    constants::{CLIENT_ID, GRAPHQL_URL, LOCALE, REDIRECT_URI, TOKEN_URL},
-    error::{Error, Result},
+    cassette::Cassette,
+    constants::{
+        AUTH_URL, CLIENT_ID, CONNECT_TIMEOUT, GRAPHQL_URL, LOCALE, PAGE_SIZE, REDIRECT_URI,
+        REFRESH_MARGIN, TIMEOUT,
+    },
+    error::{Error, GraphqlError, Result},
     publication::{self, Publication},
     queries::{
-        get_identity_config, get_publications_list,
+        get_identity_config, get_logged_in_user, get_publications_list,
         post_application::{self, HousingApplyState},
-        GetIdentityConfig, GetPublicationsList, GraphqlResponse, PostApplication,
+        GetIdentityConfig, GetLoggedInUser, GetPublicationsList, PostApplication,
     },
+    rate_limit::RateLimiter,
+    retry::{self, RetryPolicy},
     tokens::{LoginResponse, Tokens},
+    transport::{Exchange, RequestHook, ResponseHook, Transport},
+    user::{self, User},
 };
 
+fn http_client(connect_timeout: StdDuration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(connect_timeout)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Whether the operation of a GraphQL request is a mutation, which must not be retried blindly.
+fn is_mutation<V>(query: &QueryBody<V>) -> bool {
+    let mut words = query
+        .query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty());
+
+    let mut previous = None;
+
+    words.any(|word| {
+        let found = previous == Some("mutation") && word == query.operation_name;
+        previous = Some(word);
+
+        found
+    })
+}
+
+/// Fetch up to `max` publications, requesting them a page at a time with `fetch_page(first, after)`.
+async fn paginate<F, Fut>(max: i64, fetch_page: F) -> Result<Vec<Publication>>
+where
+    F: Fn(i64, Option<String>) -> Fut,
+    Fut: Future<Output = Result<(Vec<Publication>, Option<String>)>>,
+{
+    let mut publications = Vec::new();
+    let mut after = None;
+
+    loop {
+        let remaining = max.saturating_sub(publications.len() as i64);
+
+        if remaining <= 0 {
+            break;
+        }
+
+        let (page, next_cursor) = fetch_page(remaining.min(PAGE_SIZE), after).await?;
+
+        publications.extend(page);
+
+        match next_cursor {
+            Some(cursor) => after = Some(cursor),
+            None => break,
+        }
+    }
+
+    Ok(publications)
+}
+
+/// The data of a GraphQL response, together with the errors of the fields that could not be resolved.
+/// Fields that could not be resolved are null in the data, when they are nullable.
+#[derive(Debug)]
+pub struct GraphqlResponse<T> {
+    pub data: T,
+    pub errors: Vec<GraphqlError>,
+}
+
+impl<T> GraphqlResponse<T> {
+    /// Convert the data, explaining a failed conversion with the GraphQL errors if there are any.
+    /// Errors are only logged when the conversion succeeds, so partial data can still be used.
+    fn convert<U, F: FnOnce(T) -> Result<U>>(self, convert: F) -> Result<U> {
+        let GraphqlResponse { data, errors } = self;
+
+        match convert(data) {
+            Ok(value) => {
+                for error in errors.iter() {
+                    log::warn!("SSHN API returned partial data: {}", error);
+                }
+
+                Ok(value)
+            }
+            Err(error) if errors.is_empty() => Err(error),
+            Err(_) => Err(Error::GraphQL(errors)),
+        }
+    }
+}
+
 #[async_trait]
-pub trait Client {
-    async fn get_publications_list(&mut self, max: i64) -> Result<Vec<Publication>>;
+pub trait Client: Send + Sync {
+    async fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>>;
 }
 
+/// A client for the public parts of the SSHN API.
+/// Cloning is cheap, clones share their connection pool, rate limiter and cassette.
+#[derive(Clone)]
 pub struct UnAuthenticatedClient {
     graphql_url: String,
+    auth_url: String,
     http_client: reqwest::Client,
+    /// Whether the HTTP client was set using [`Self::http_client`], instead of being built by this client.
+    custom_http_client: bool,
+    transport: Option<Arc<dyn Transport>>,
+    request_hooks: Vec<RequestHook>,
+    response_hooks: Vec<ResponseHook>,
+    locale: String,
+    cassette: Option<Arc<Cassette>>,
+    timeout: StdDuration,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 pub enum LoginType {
@@ -36,10 +147,225 @@ impl UnAuthenticatedClient {
     pub fn new(graphql_url: Option<String>) -> Self {
         Self {
             graphql_url: graphql_url.unwrap_or(GRAPHQL_URL.to_string()),
-            http_client: reqwest::Client::new(),
+            auth_url: AUTH_URL.to_string(),
+            http_client: http_client(StdDuration::from_secs(CONNECT_TIMEOUT)),
+            custom_http_client: false,
+            transport: None,
+            request_hooks: Vec::new(),
+            response_hooks: Vec::new(),
+            locale: LOCALE.to_string(),
+            cassette: None,
+            timeout: StdDuration::from_secs(TIMEOUT),
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: Some(Arc::new(RateLimiter::default())),
+        }
+    }
+
+    /// Set the locale used for localized texts returned by the SSHN API, e.g. `nl-NL`.
+    pub fn locale<L: Into<String>>(self, locale: L) -> Self {
+        Self {
+            locale: locale.into(),
+            ..self
+        }
+    }
+
+    /// Set the base url of the OpenID Connect endpoints of the identity provider.
+    pub fn auth_url<U: Into<String>>(self, auth_url: U) -> Self {
+        Self {
+            auth_url: auth_url.into(),
+            ..self
+        }
+    }
+
+    /// Record all requests and responses to, or replay them from, the given cassette.
+    pub fn cassette<C: Into<Arc<Cassette>>>(self, cassette: C) -> Self {
+        Self {
+            cassette: Some(cassette.into()),
+            ..self
+        }
+    }
+
+    /// Set how long a single attempt at a request may take, retries get their own timeout.
+    pub fn timeout(self, timeout: StdDuration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Set how long connecting to the server may take.
+    /// Has no effect on a client set using [`Self::http_client`], set the connect timeout on that client instead.
+    pub fn connect_timeout(self, connect_timeout: StdDuration) -> Self {
+        if self.custom_http_client {
+            log::warn!("Ignoring the connect timeout, as a custom HTTP client is used");
+
+            return self;
+        }
+
+        Self {
+            http_client: http_client(connect_timeout),
+            ..self
+        }
+    }
+
+    /// Send requests using a preconfigured HTTP client, e.g. with a proxy, custom root certificates or user agent.
+    /// Its connect timeout is used, regardless of [`Self::connect_timeout`].
+    pub fn http_client(self, http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+            custom_http_client: true,
+            ..self
+        }
+    }
+
+    /// Send requests using the given transport instead of the HTTP client, e.g. to wrap them in middleware.
+    pub fn transport<T: Transport + 'static>(self, transport: T) -> Self {
+        Self {
+            transport: Some(Arc::new(transport)),
+            ..self
+        }
+    }
+
+    /// Add a hook that is called with every request right before it is sent, e.g. to add tracing headers.
+    pub fn on_request<F: Fn(&mut reqwest::Request) + Send + Sync + 'static>(self, hook: F) -> Self {
+        let mut request_hooks = self.request_hooks;
+
+        request_hooks.push(Arc::new(hook));
+
+        Self {
+            request_hooks,
+            ..self
+        }
+    }
+
+    /// Add a hook that is called after every attempt at a request, e.g. to record metrics.
+    pub fn on_response<F: Fn(&Exchange) + Send + Sync + 'static>(self, hook: F) -> Self {
+        let mut response_hooks = self.response_hooks;
+
+        response_hooks.push(Arc::new(hook));
+
+        Self {
+            response_hooks,
+            ..self
+        }
+    }
+
+    /// Set when and how failed requests are retried.
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Set the rate limiter requests have to pass. Clients given the same limiter are limited together.
+    pub fn rate_limiter<L: Into<Arc<RateLimiter>>>(self, rate_limiter: L) -> Self {
+        Self {
+            rate_limiter: Some(rate_limiter.into()),
+            ..self
+        }
+    }
+
+    /// Send requests without any rate limit.
+    pub fn without_rate_limit(self) -> Self {
+        Self {
+            rate_limiter: None,
+            ..self
+        }
+    }
+
+    /// Send a request, retrying it according to the retry policy.
+    /// Requests that are not idempotent are only retried when they were certainly not processed.
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        idempotent: bool,
+    ) -> Result<reqwest::Response> {
+        let request = request.timeout(self.timeout).build()?;
+
+        let mut retry = 0;
+
+        loop {
+            // Requests with a streaming body can not be sent again.
+            let Some(attempt) = request.try_clone() else {
+                return self.send_once(request, retry).await;
+            };
+
+            let result = self.send_once(attempt, retry).await;
+
+            let retry_after = match &result {
+                Ok(response) if RetryPolicy::should_retry_status(response.status(), idempotent) => {
+                    retry::retry_after(response)
+                }
+                Err(Error::HttpRequest(error))
+                    if RetryPolicy::should_retry_error(error, idempotent) =>
+                {
+                    None
+                }
+                _ => return result,
+            };
+
+            let Some(delay) = self.retry_policy.delay(retry, retry_after) else {
+                return result;
+            };
+
+            log::debug!(
+                "Request to {} failed, retrying in {:?}",
+                request.url(),
+                delay
+            );
+
+            tokio::time::sleep(delay).await;
+
+            retry += 1;
         }
     }
 
+    async fn send_once(
+        &self,
+        mut request: reqwest::Request,
+        attempt: u32,
+    ) -> Result<reqwest::Response> {
+        if let Some(rate_limiter) = self.rate_limiter.as_ref() {
+            rate_limiter.acquire().await;
+        }
+
+        for hook in self.request_hooks.iter() {
+            hook(&mut request);
+        }
+
+        let method = request.method().clone();
+        let url = request.url().clone();
+
+        let transport = self
+            .transport
+            .as_deref()
+            .unwrap_or(&self.http_client as &dyn Transport);
+
+        let start = Instant::now();
+
+        let result = match self.cassette.as_ref() {
+            Some(cassette) => cassette.send(transport, request).await,
+            None => transport.execute(request).await,
+        };
+
+        let exchange = Exchange {
+            method: &method,
+            url: &url,
+            status: result.as_ref().ok().map(reqwest::Response::status),
+            error: result.as_ref().err(),
+            elapsed: start.elapsed(),
+            attempt,
+        };
+
+        for hook in self.response_hooks.iter() {
+            hook(&exchange);
+        }
+
+        result
+    }
+
+    fn auth_endpoint(&self, endpoint: &str) -> String {
+        format!("{}/{}", self.auth_url.trim_end_matches('/'), endpoint)
+    }
+
     pub async fn auth(&self, login_type: LoginType) -> Result<Tokens> {
         let mut params = HashMap::new();
 
@@ -50,7 +376,7 @@ impl UnAuthenticatedClient {
                 params.insert("grant_type", "authorization_code");
                 params.insert("redirect_uri", REDIRECT_URI);
 
-                params.insert("code_verifier", &verifier);
+                params.insert("code_verifier", verifier);
                 params.insert("code", code);
             }
             LoginType::RefreshToken { token } => {
@@ -60,23 +386,24 @@ impl UnAuthenticatedClient {
             LoginType::Password { username, password } => {
                 params.insert("grant_type", "password");
 
-                params.insert("username", &username);
-                params.insert("password", &password);
+                params.insert("username", username);
+                params.insert("password", password);
             }
         };
 
         let body = serde_urlencoded::to_string(&params)?;
 
-        let response = self
+        let request = self
             .http_client
-            .post(TOKEN_URL)
+            .post(self.auth_endpoint("token"))
             .body(body)
             .header(
                 reqwest::header::CONTENT_TYPE,
                 "application/x-www-form-urlencoded",
-            )
-            .send()
-            .await?;
+            );
+
+        // Token grants are not idempotent, a refresh token can only be used once.
+        let response = self.send(request, false).await?;
 
         if let Err(err) = response.error_for_status_ref() {
             log::debug!("{}", response.text().await?);
@@ -89,35 +416,114 @@ impl UnAuthenticatedClient {
         Ok(response_data.into())
     }
 
+    /// End the session belonging to the given refresh token, revoking all of its tokens.
+    pub async fn logout<T: AsRef<str>>(&self, refresh_token: T) -> Result<()> {
+        let mut params = HashMap::new();
+
+        params.insert("client_id", CLIENT_ID);
+        params.insert("refresh_token", refresh_token.as_ref());
+
+        let body = serde_urlencoded::to_string(&params)?;
+
+        let request = self
+            .http_client
+            .post(self.auth_endpoint("logout"))
+            .body(body)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            );
+
+        let response = self.send(request, true).await?;
+
+        if let Err(err) = response.error_for_status_ref() {
+            log::debug!("{}", response.text().await?);
+
+            return Err(Error::HttpRequest(err));
+        };
+
+        Ok(())
+    }
+
+    /// Use previously obtained tokens to authenticate, without logging in again.
+    pub fn authenticate(self, tokens: Tokens) -> AuthenticatedClient {
+        AuthenticatedClient::from_client(self, tokens)
+    }
+
     pub async fn login(self, login_type: LoginType) -> Result<AuthenticatedClient> {
         let tokens = self.auth(login_type).await?;
 
-        let authenticated_client = AuthenticatedClient {
-            client: self,
-            tokens,
-        };
+        let authenticated_client = AuthenticatedClient::from_client(self, tokens);
 
         Ok(authenticated_client)
     }
 
-    async fn query<T: DeserializeOwned, Q: Serialize>(
+    async fn query<T: DeserializeOwned, V: Serialize>(
         &self,
-        query: &Q,
+        query: &QueryBody<V>,
         access_token: Option<&str>,
-    ) -> Result<T> {
+    ) -> Result<GraphqlResponse<T>> {
         let mut request = self.http_client.post(&self.graphql_url).json(query);
 
         if let Some(access_token) = access_token {
             request = request.bearer_auth(access_token)
         }
 
-        let response = request.send().await?;
+        let response = self.send(request, !is_mutation(query)).await?;
+
+        if let Err(error) = response.error_for_status_ref() {
+            // Invalid queries are rejected with a client error, explained by the errors in the body.
+            if response.status() == reqwest::StatusCode::UNAUTHORIZED
+                || !response.status().is_client_error()
+            {
+                return Err(Error::HttpRequest(error));
+            }
+
+            let body = response.text().await?;
+
+            return match serde_json::from_str::<graphql_client::Response<Value>>(&body) {
+                Ok(graphql_client::Response {
+                    errors: Some(errors),
+                    ..
+                }) if !errors.is_empty() => Err(Error::GraphQL(errors)),
+                _ => Err(Error::HttpRequest(error)),
+            };
+        }
+
+        let response_body = response.json::<graphql_client::Response<T>>().await?;
+
+        let errors = response_body.errors.unwrap_or_default();
+
+        match response_body.data {
+            Some(data) => Ok(GraphqlResponse { data, errors }),
+            None if errors.is_empty() => Err(Error::Api(String::from(
+                "The response contained neither data nor errors",
+            ))),
+            None => Err(Error::GraphQL(errors)),
+        }
+    }
+
+    /// Get the key set used by the identity provider to sign its tokens.
+    #[cfg(feature = "jwks")]
+    pub async fn get_jwks(&self) -> Result<jsonwebtoken::jwk::JwkSet> {
+        let request = self.http_client.get(self.auth_endpoint("certs"));
+
+        let response = self.send(request, true).await?.error_for_status()?;
 
-        let response = response.error_for_status()?;
+        let jwks = response.json().await?;
 
-        let response_body = response.json::<GraphqlResponse<T>>().await?;
+        Ok(jwks)
+    }
+
+    /// Execute any GraphQL operation against the SSHN API, without authentication.
+    /// As long as data was returned, the errors of fields that could not be resolved are returned with it.
+    pub async fn execute<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<GraphqlResponse<Q::ResponseData>> {
+        let request_body = Q::build_query(variables);
 
-        Ok(response_body.data)
+        self.query(&request_body, None).await
     }
 
     pub async fn get_endpoints(&self) -> Result<get_identity_config::ResponseData> {
@@ -127,102 +533,277 @@ impl UnAuthenticatedClient {
 
         let request_body = GetIdentityConfig::build_query(variables);
 
-        let data = self.query(&request_body, None).await?;
-
-        Ok(data)
+        self.query(&request_body, None).await?.convert(Ok)
     }
 }
 
 #[async_trait]
 impl Client for UnAuthenticatedClient {
-    async fn get_publications_list(&mut self, max: i64) -> Result<Vec<Publication>> {
-        let variables = get_publications_list::Variables {
-            order_by: Some(get_publications_list::HousingPublicationsOrder::STARTDATE_ASC),
-            first: Some(max),
-            locale: Some(String::from(LOCALE)),
-            after: None,
-            where_: None,
-        };
-
-        let request_body = GetPublicationsList::build_query(variables);
+    async fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>> {
+        paginate(max, |first, after| async move {
+            let variables = get_publications_list::Variables {
+                order_by: Some(get_publications_list::HousingPublicationsOrder::STARTDATE_ASC),
+                first: Some(first),
+                locale: Some(self.locale.clone()),
+                after,
+                where_: None,
+            };
 
-        let data: get_publications_list::ResponseData = self.query(&request_body, None).await?;
+            let request_body = GetPublicationsList::build_query(variables);
 
-        let publications = publication::convert_publications(data)?;
+            let response: GraphqlResponse<get_publications_list::ResponseData> =
+                self.query(&request_body, None).await?;
 
-        Ok(publications)
+            response.convert(publication::convert_publications_page)
+        })
+        .await
     }
 }
 
+/// Callback that is called with the new tokens every time they are refreshed.
+pub type TokensChangedCallback = Arc<dyn Fn(&Tokens) + Send + Sync>;
+
+/// The tokens shared by all clones of an authenticated client.
+struct Session {
+    tokens: RwLock<Tokens>,
+    /// Held while refreshing, so concurrent requests refresh the tokens only once.
+    refreshing: AsyncMutex<()>,
+}
+
+/// A client for the SSHN API that is logged in, refreshing its tokens when needed.
+/// Cloning is cheap, clones share their tokens, so they can be used from concurrent tasks.
+#[derive(Clone)]
 pub struct AuthenticatedClient {
     client: UnAuthenticatedClient,
-    tokens: Tokens,
+    session: Arc<Session>,
+    refresh_margin: Duration,
+    on_tokens_changed: Option<TokensChangedCallback>,
 }
 
-impl Into<Tokens> for AuthenticatedClient {
-    fn into(self) -> Tokens {
-        self.tokens
+impl From<AuthenticatedClient> for Tokens {
+    fn from(client: AuthenticatedClient) -> Self {
+        client.tokens()
     }
 }
 
 impl AuthenticatedClient {
     pub fn new(graphql_url: Option<String>, tokens: Tokens) -> Self {
+        Self::from_client(UnAuthenticatedClient::new(graphql_url), tokens)
+    }
+
+    fn from_client(client: UnAuthenticatedClient, tokens: Tokens) -> Self {
+        Self {
+            client,
+            session: Arc::new(Session {
+                tokens: RwLock::new(tokens),
+                refreshing: AsyncMutex::new(()),
+            }),
+            refresh_margin: Duration::seconds(REFRESH_MARGIN),
+            on_tokens_changed: None,
+        }
+    }
+
+    /// Set the locale used for localized texts returned by the SSHN API, e.g. `nl-NL`.
+    pub fn locale<L: Into<String>>(self, locale: L) -> Self {
         Self {
-            client: UnAuthenticatedClient::new(graphql_url),
-            tokens,
+            client: self.client.locale(locale),
+            ..self
         }
     }
 
-    async fn check_expiration(&mut self) -> Result<()> {
-        if self.tokens.access_token().has_expired() {
-            if !self.tokens.refresh_token().has_expired() {
-                let token = self.tokens.refresh_token().content().to_string();
+    /// Set how long before the access token expires it should already be refreshed.
+    pub fn refresh_margin(self, refresh_margin: Duration) -> Self {
+        Self {
+            refresh_margin,
+            ..self
+        }
+    }
 
-                self.tokens = self.client.auth(LoginType::RefreshToken { token }).await?;
-            } else {
-                return Err(Error::TokenExpired);
-            }
+    /// Set a callback that is called with the new tokens every time they are refreshed,
+    /// so they can be persisted.
+    pub fn on_tokens_changed<F: Fn(&Tokens) + Send + Sync + 'static>(self, callback: F) -> Self {
+        Self {
+            on_tokens_changed: Some(Arc::new(callback)),
+            ..self
+        }
+    }
+
+    /// End the current session, revoking the tokens of this client and all of its clones.
+    pub async fn logout(self) -> Result<UnAuthenticatedClient> {
+        let refresh_token = self.tokens().refresh_token().content().to_string();
+
+        self.client.logout(refresh_token).await?;
+
+        Ok(self.client)
+    }
+
+    /// Get new tokens using the current refresh token.
+    pub async fn refresh(&self) -> Result<()> {
+        self.refresh_if(|_| true).await
+    }
+
+    /// Refresh the tokens if they still need it once no other refresh is in progress,
+    /// as a concurrent refresh may already have replaced them.
+    async fn refresh_if<F: Fn(&Tokens) -> bool>(&self, needs_refresh: F) -> Result<()> {
+        let _refreshing = self.session.refreshing.lock().await;
+
+        let tokens = self.tokens();
+
+        if !needs_refresh(&tokens) {
+            return Ok(());
+        }
+
+        if tokens.refresh_token().has_expired() {
+            return Err(Error::TokenExpired);
+        }
+
+        let token = tokens.refresh_token().content().to_string();
+
+        let tokens = self.client.auth(LoginType::RefreshToken { token }).await?;
+
+        if let Some(callback) = &self.on_tokens_changed {
+            callback(&tokens);
         }
 
+        *self.session.tokens.write().unwrap() = tokens;
+
         Ok(())
     }
 
-    async fn query<Q: Serialize, T: DeserializeOwned>(&mut self, query: &Q) -> Result<T> {
+    fn should_refresh(&self, tokens: &Tokens) -> bool {
+        let access_token = tokens.access_token();
+
+        if !access_token.expires_within(self.refresh_margin) {
+            return false;
+        }
+
+        // The access token can still be used for a little while, even if it can no longer be refreshed.
+        access_token.has_expired() || !tokens.refresh_token().has_expired()
+    }
+
+    async fn check_expiration(&self) -> Result<()> {
+        if !self.should_refresh(&self.tokens()) {
+            return Ok(());
+        }
+
+        self.refresh_if(|tokens| self.should_refresh(tokens)).await
+    }
+
+    fn access_token(&self) -> String {
+        self.session
+            .tokens
+            .read()
+            .unwrap()
+            .access_token()
+            .content()
+            .to_string()
+    }
+
+    async fn query<V: Serialize, T: DeserializeOwned>(
+        &self,
+        query: &QueryBody<V>,
+    ) -> Result<GraphqlResponse<T>> {
         self.check_expiration().await?;
 
-        self.client
-            .query(query, Some(self.tokens.access_token().content()))
-            .await
+        let access_token = self.access_token();
+
+        let result = self.client.query(query, Some(&access_token)).await;
+
+        match result {
+            Err(error) if error.is_unauthorized() => {
+                // The access token might have been revoked before its expiry date, so try once more with new tokens.
+                log::debug!("Access token was rejected, refreshing tokens and retrying");
+
+                // The token endpoint rejects revoked refresh tokens with a client error.
+                self.refresh_if(|tokens| tokens.access_token().content() == access_token)
+                    .await
+                    .map_err(|error| match error {
+                        Error::HttpRequest(error)
+                            if error
+                                .status()
+                                .is_some_and(|status| status.is_client_error()) =>
+                        {
+                            Error::Unauthorized
+                        }
+                        error => error,
+                    })?;
+
+                self.client
+                    .query(query, Some(&self.access_token()))
+                    .await
+                    .map_err(|error| {
+                        if error.is_unauthorized() {
+                            Error::Unauthorized
+                        } else {
+                            error
+                        }
+                    })
+            }
+            result => result,
+        }
     }
 
-    pub fn tokens(&self) -> &Tokens {
-        &self.tokens
+    /// The current tokens, which change every time they are refreshed.
+    pub fn tokens(&self) -> Tokens {
+        self.session.tokens.read().unwrap().clone()
     }
 
     pub fn client(&self) -> &UnAuthenticatedClient {
         &self.client
     }
 
+    /// Execute any GraphQL operation against the SSHN API, as the logged in user.
+    /// As long as data was returned, the errors of fields that could not be resolved are returned with it.
+    pub async fn execute<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<GraphqlResponse<Q::ResponseData>> {
+        let request_body = Q::build_query(variables);
+
+        self.query(&request_body).await
+    }
+
+    /// Get the account details of the logged in user.
+    pub async fn get_user(&self) -> Result<User> {
+        let variables = get_logged_in_user::Variables {
+            locale: Some(self.client.locale.clone()),
+        };
+
+        let request_body = GetLoggedInUser::build_query(variables);
+
+        let response: GraphqlResponse<get_logged_in_user::ResponseData> =
+            self.query(&request_body).await?;
+
+        response.convert(user::convert_user)
+    }
+
     /// Reply to a publication, given that publications id.
-    pub async fn reply_to_publication<I: Into<String>>(&mut self, publication_id: I) -> Result<()> {
+    pub async fn reply_to_publication<I: Into<String>>(&self, publication_id: I) -> Result<()> {
         let variables = post_application::Variables {
             publication_id: publication_id.into(),
-            locale: Some(String::from(LOCALE)),
+            locale: Some(self.client.locale.clone()),
         };
 
         let request_body = PostApplication::build_query(variables);
 
-        let data: post_application::ResponseData = self.query(&request_body).await?;
+        let response: GraphqlResponse<post_application::ResponseData> =
+            self.query(&request_body).await?;
 
-        if let Some(unit) = data.housing_apply_to_unit {
-            match unit.state {
-                HousingApplyState::OK => {}
-                _ => {
-                    let error = Error::Api(unit.description.unwrap_or(String::new()));
+        // The state of a reply is not always returned, without errors explaining why the reply is accepted.
+        if response.data.housing_apply_to_unit.is_none() && response.errors.is_empty() {
+            return Ok(());
+        }
 
-                    return Err(error);
-                }
-            };
+        let unit =
+            response.convert(|data| data.housing_apply_to_unit.ok_or(Error::MissingReplyState))?;
+
+        match unit.state {
+            HousingApplyState::OK => {}
+            _ => {
+                let error = Error::Api(unit.description.unwrap_or(String::new()));
+
+                return Err(error);
+            }
         };
 
         Ok(())
@@ -231,21 +812,313 @@ impl AuthenticatedClient {
 
 #[async_trait]
 impl Client for AuthenticatedClient {
-    async fn get_publications_list(&mut self, max: i64) -> Result<Vec<Publication>> {
-        let variables = get_publications_list::Variables {
-            order_by: Some(get_publications_list::HousingPublicationsOrder::STARTDATE_ASC),
-            first: Some(max),
-            locale: Some(String::from(LOCALE)),
-            after: None,
-            where_: None,
+    async fn get_publications_list(&self, max: i64) -> Result<Vec<Publication>> {
+        paginate(max, |first, after| async move {
+            let variables = get_publications_list::Variables {
+                order_by: Some(get_publications_list::HousingPublicationsOrder::STARTDATE_ASC),
+                first: Some(first),
+                locale: Some(self.client.locale.clone()),
+                after,
+                where_: None,
+            };
+
+            let request_body = GetPublicationsList::build_query(variables);
+
+            let response: GraphqlResponse<get_publications_list::ResponseData> =
+                self.query(&request_body).await?;
+
+            response.convert(publication::convert_publications_page)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        test_support::*,
+        tokens::{Token, TokenType},
+    };
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let total = PAGE_SIZE as usize * 2 + 10;
+        let requests = Mutex::new(Vec::new());
+
+        let fetch_page = |first: i64, after: Option<String>| {
+            requests.lock().unwrap().push(first);
+
+            let start = after.map_or(0, |cursor| cursor.parse().unwrap());
+            let end = (start + first as usize).min(total);
+
+            let next_cursor = (end < total).then(|| end.to_string());
+
+            async move {
+                Ok((
+                    (start..end)
+                        .map(|id| PublicationBuilder::new(id.to_string()).build())
+                        .collect(),
+                    next_cursor,
+                ))
+            }
+        };
+
+        let publications = paginate(i64::MAX, fetch_page).await.unwrap();
+
+        assert_eq!(publications.len(), total);
+        assert_eq!(publications[total - 1].id(), (total - 1).to_string());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        requests.lock().unwrap().clear();
+
+        let publications = paginate(PAGE_SIZE + 1, fetch_page).await.unwrap();
+
+        assert_eq!(publications.len(), PAGE_SIZE as usize + 1);
+        assert_eq!(*requests.lock().unwrap(), [PAGE_SIZE, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let server = MockServer::start().await.unwrap();
+
+        let policy = retry::RetryPolicy::default().initial_backoff(std::time::Duration::ZERO);
+
+        server.fail_next([503, 429]);
+
+        let publications = server
+            .client()
+            .retry_policy(policy)
+            .get_publications_list(5)
+            .await
+            .unwrap();
+
+        assert_eq!(publications.len(), 2);
+        assert_eq!(server.requests().len(), 3);
+
+        server.fail_next([500]);
+
+        let result = server
+            .client()
+            .retry_policy(retry::RetryPolicy::none())
+            .get_publications_list(5)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_on_unauthorized() {
+        let server = MockServer::start().await.unwrap();
+
+        let client = server.login().await.unwrap();
+
+        // The access token is rejected, so the tokens are refreshed and the query is sent again.
+        server.fail_next([401]);
+
+        client.get_user().await.unwrap();
+
+        let requests = server.requests();
+        let paths: Vec<&str> = requests[1..]
+            .iter()
+            .map(|request| request.path.as_str())
+            .collect();
+
+        assert_eq!(paths, ["/graphql", "/auth/token", "/graphql"]);
+        assert!(requests[2].body.contains("grant_type=refresh_token"));
+
+        // When the refresh token is rejected as well, the session is over.
+        server.fail_next([401, 401]);
+
+        let error = client.get_user().await.unwrap_err();
+
+        assert!(matches!(error, Error::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_of_mutations() {
+        let server = MockServer::start().await.unwrap();
+
+        let policy = retry::RetryPolicy::default().initial_backoff(std::time::Duration::ZERO);
+
+        server.fail_next([503]);
+
+        let result = server
+            .client()
+            .retry_policy(policy.clone())
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 1);
+
+        let client = server
+            .client()
+            .retry_policy(policy)
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+
+        server.fail_next([503]);
+
+        assert!(client.reply_to_publication(PUBLICATION_ID).await.is_err());
+
+        let applications = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.operation_name().as_deref() == Some("PostApplication"))
+            .count();
+
+        assert_eq!(applications, 1);
+
+        // Rate limited requests were not processed, so even mutations are retried.
+        server.fail_next([429]);
+
+        client.reply_to_publication(PUBLICATION_ID).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transport_and_hooks() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
         };
 
-        let request_body = GetPublicationsList::build_query(variables);
+        struct Counting(Arc<AtomicUsize>, reqwest::Client);
+
+        #[async_trait::async_trait]
+        impl Transport for Counting {
+            async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+
+                Ok(self.1.execute(request).await?)
+            }
+        }
+
+        let server = MockServer::start().await.unwrap();
+
+        let sent = Arc::new(AtomicUsize::new(0));
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+
+        server.fail_next([503]);
+
+        let client = server
+            .client()
+            .retry_policy(retry::RetryPolicy::default().initial_backoff(std::time::Duration::ZERO))
+            .transport(Counting(Arc::clone(&sent), reqwest::Client::new()))
+            .on_request(|request| {
+                request.headers_mut().insert(
+                    "x-request-id",
+                    reqwest::header::HeaderValue::from_static("42"),
+                );
+            })
+            .on_response({
+                let attempts = Arc::clone(&attempts);
+
+                move |exchange| {
+                    attempts.lock().unwrap().push((
+                        exchange.attempt,
+                        exchange.status.map(|status| status.as_u16()),
+                    ));
+                }
+            });
+
+        client.get_publications_list(5).await.unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+        assert_eq!(*attempts.lock().unwrap(), [(0, Some(503)), (1, Some(200))]);
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.header("x-request-id") == Some("42")));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refresh() {
+        let server = MockServer::start().await.unwrap();
+
+        let now = chrono::Utc::now();
+
+        let tokens = Tokens::new(
+            Token::new(
+                "refresh",
+                now + chrono::Duration::hours(1),
+                TokenType::Refresh,
+            ),
+            Token::new("expired", now, TokenType::Access),
+        );
+
+        let client = server.client().authenticate(tokens);
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+
+                tokio::spawn(async move { client.get_user().await })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let refreshes = server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/auth/token")
+            .count();
+
+        assert_eq!(refreshes, 1);
+        assert_ne!(client.tokens().access_token().content(), "expired");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_margin() {
+        let server = MockServer::start().await.unwrap();
+
+        let now = chrono::Utc::now();
+        let refresh_token = Token::new(
+            "refresh",
+            now + chrono::Duration::hours(1),
+            TokenType::Refresh,
+        );
+
+        // Still valid, but expiring within the refresh margin, so it is refreshed before it is used.
+        let expiring = now + chrono::Duration::seconds(REFRESH_MARGIN / 2);
+
+        let client = server.client().authenticate(Tokens::new(
+            refresh_token.clone(),
+            Token::new("expiring", expiring, TokenType::Access),
+        ));
+
+        client.get_user().await.unwrap();
+
+        let requests = server.requests();
+
+        assert_eq!(requests[0].path, "/auth/token");
+        assert_ne!(requests[1].header("authorization"), Some("Bearer expiring"));
+
+        // Outside of the margin the access token is used as is.
+        let valid = now + chrono::Duration::seconds(REFRESH_MARGIN * 2);
+
+        let client = server.client().authenticate(Tokens::new(
+            refresh_token,
+            Token::new("valid", valid, TokenType::Access),
+        ));
 
-        let data: get_publications_list::ResponseData = self.query(&request_body).await?;
+        client.get_user().await.unwrap();
 
-        let publications = publication::convert_publications(data)?;
+        let requests = server.requests();
 
-        Ok(publications)
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].header("authorization"), Some("Bearer valid"));
     }
 }
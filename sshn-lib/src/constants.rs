@@ -1,10 +1,22 @@
 pub const GRAPHQL_URL: &str = "https://gateway.embracecloud.nl/graphql";
 
-pub const TOKEN_URL: &str =
-    "https://auth.embracecloud.nl/auth/realms/sshn/protocol/openid-connect/token";
+/// The OpenID Connect endpoints of the identity provider, e.g. `{AUTH_URL}/token`.
+pub const AUTH_URL: &str = "https://auth.embracecloud.nl/auth/realms/sshn/protocol/openid-connect";
 
 pub const REDIRECT_URI: &str = "https://mijn.sshn.nl/authentication/callback";
 
 pub const LOCALE: &str = "en-US";
 
 pub const CLIENT_ID: &str = "portal-legacy";
+
+/// Amount of seconds before the access token expires that it will already be refreshed.
+pub const REFRESH_MARGIN: i64 = 30;
+
+/// Amount of seconds a single request to the SSHN API may take.
+pub const TIMEOUT: u64 = 30;
+
+/// Amount of seconds connecting to the SSHN API may take.
+pub const CONNECT_TIMEOUT: u64 = 10;
+
+/// The most publications requested at once, longer lists are fetched a page at a time.
+pub const PAGE_SIZE: i64 = 50;
@@ -1,5 +1,7 @@
 use std::result;
 
+pub use graphql_client::Error as GraphqlError;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Error from SSHN API: {0}")]
@@ -10,14 +12,68 @@ pub enum Error {
     HttpRequest(#[from] reqwest::Error),
     #[error("The refresh token expired")]
     TokenExpired,
+    #[error("The SSHN API rejected the access token")]
+    Unauthorized,
     #[error("Missing refresh token to get new tokens")]
     MissingRefreshToken,
     #[error("SSHN Api did not return valid publications")]
     MissingPublications,
+    #[error("SSHN Api did not return the logged in user")]
+    MissingUser,
+    #[error("SSHN Api did not return the state of the reply")]
+    MissingReplyState,
+    #[error("GraphQL error: {}", join_errors(.0))]
+    GraphQL(Vec<GraphqlError>),
     #[error("The authentication endpoint is missing")]
     NoAuthUrl,
     #[error("Failed to parse url: {0}")]
     ParseUrl(#[from] url::ParseError),
+    #[error("Token is not a valid JWT")]
+    InvalidJwt,
+    #[error("Failed to deserialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Cassette error: {0}")]
+    Cassette(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "blocking")]
+    #[error("Failed to start the runtime of the blocking client: {0}")]
+    Runtime(std::io::Error),
+    #[error("Failed to parse ranking rules: {0}")]
+    Rules(#[from] toml::de::Error),
+    #[cfg(feature = "jwks")]
+    #[error("Failed to verify JWT: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+}
+
+impl Error {
+    /// Whether this error was caused by the server responding with `401 Unauthorized`.
+    pub fn is_unauthorized(&self) -> bool {
+        match self {
+            Error::Unauthorized => true,
+            Error::HttpRequest(error) => error.status() == Some(reqwest::StatusCode::UNAUTHORIZED),
+            Error::GraphQL(errors) => errors.iter().any(|error| {
+                error
+                    .extensions
+                    .as_ref()
+                    .and_then(|extensions| extensions.get("code"))
+                    .and_then(|code| code.as_str())
+                    .is_some_and(|code| UNAUTHENTICATED_CODES.contains(&code))
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// Error codes used by GraphQL servers when the request is not authenticated.
+const UNAUTHENTICATED_CODES: &[&str] = &["AUTH_NOT_AUTHENTICATED", "UNAUTHENTICATED"];
+
+fn join_errors(errors: &[GraphqlError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 pub type Result<T> = result::Result<T, Error>;
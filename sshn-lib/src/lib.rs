@@ -1,54 +1,212 @@
 mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cassette;
+mod claims;
 mod client;
 mod constants;
 pub mod error;
 mod queries;
+pub mod ranking;
+pub mod rate_limit;
+pub mod retry;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 mod tokens;
+pub mod transport;
 mod utils;
 
 pub use api::*;
 
+/// The version of `graphql_client` used by the clients, to derive own operations with.
+pub use graphql_client;
+
+/// The bundled GraphQL schema of the SSHN API, e.g. to write to a file in a build script,
+/// so own operations can be derived against it and executed using `execute`.
+pub const SCHEMA: &str = include_str!("../schema.graphql");
+
 pub use {
-    client::{AuthenticatedClient, Client, LoginType, UnAuthenticatedClient},
+    claims::{Claims, RealmAccess},
+    client::{
+        AuthenticatedClient, Client, GraphqlResponse, LoginType, TokensChangedCallback,
+        UnAuthenticatedClient,
+    },
+    constants::REDIRECT_URI,
     tokens::{Token, TokenType, Tokens},
     utils::{generate_auth_url, get_code_challenge},
 };
 
 #[cfg(test)]
 mod tests {
-
-    use crate::client::LoginType;
+    use crate::{client::LoginType, test_support::*};
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_get_endpoints() {
+        let server = MockServer::start().await.unwrap();
+
+        let data = server.client().get_endpoints().await.unwrap();
+
+        let config = data.identity_config.unwrap();
+
+        assert_eq!(config.portal_client_id.as_deref(), Some("portal-legacy"));
+    }
+
     #[tokio::test]
     async fn test_get_publications() {
-        let client = UnAuthenticatedClient::new(None);
+        let server = MockServer::start().await.unwrap();
 
-        let data = client.get_endpoints().await.unwrap();
+        let publications = server.client().get_publications_list(5).await.unwrap();
 
-        println!("{:?}", data);
+        // The last recorded publication is incomplete, so it is skipped.
+        assert_eq!(publications.len(), 2);
+        assert_eq!(publications[0].id(), PUBLICATION_ID);
+        assert_eq!(publications[0].city(), "Nijmegen");
+        assert_eq!(publications[1].nr_of_people_with_higher_priority(), 0);
     }
 
     #[tokio::test]
     async fn test_login() {
-        dotenv::dotenv().ok();
+        let server = MockServer::start().await.unwrap();
+
+        let client = server.login().await.unwrap();
+
+        assert_eq!(client.tokens().claims().unwrap().email(), Some(USERNAME));
+        assert!(!client.tokens().access_token().has_expired());
+
+        let user = client.get_user().await.unwrap();
+
+        assert_eq!(user.name(), "Test van User");
+    }
+
+    #[tokio::test]
+    async fn test_login_wrong_password() {
+        let server = MockServer::start().await.unwrap();
+
+        let result = server
+            .client()
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: String::from("wrong"),
+            })
+            .await;
+
+        assert!(result.is_err_and(|error| error.is_unauthorized()));
+    }
+
+    #[tokio::test]
+    async fn test_post_application() {
+        let server = MockServer::start().await.unwrap();
+
+        let client = server.login().await.unwrap();
+
+        client.reply_to_publication(PUBLICATION_ID).await.unwrap();
+
+        let request = server
+            .requests()
+            .into_iter()
+            .find(|request| request.operation_name().as_deref() == Some("PostApplication"))
+            .unwrap();
 
-        let username = std::env::var("SSHN_USERNAME").unwrap();
-        let password = std::env::var("SSHN_PASSWORD").unwrap();
+        assert!(request.body.contains(PUBLICATION_ID));
+        assert_eq!(
+            request.header("authorization"),
+            Some(format!("Bearer {}", client.tokens().access_token().content()).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graphql_errors() {
+        let server = MockServer::start().await.unwrap();
 
-        let client = UnAuthenticatedClient::new(None);
+        let client = server.login().await.unwrap();
 
-        let _auth_client = client
-            .login(LoginType::Password { username, password })
+        let error = client
+            .reply_to_publication(UNKNOWN_PUBLICATION_ID)
+            .await
+            .unwrap_err();
+
+        match error {
+            error::Error::GraphQL(errors) => {
+                assert_eq!(errors[0].message, "Publication not found");
+            }
+            error => panic!("Expected GraphQL error, got {}", error),
+        }
+
+        // Without errors, a reply without a state is accepted.
+        client
+            .reply_to_publication(STATELESS_PUBLICATION_ID)
             .await
             .unwrap();
     }
 
-    // #[tokio::test]
-    // async fn test_post_application() {
-    //     let client = Client::new(None);
+    #[tokio::test]
+    async fn test_execute() {
+        mod custom {
+            use graphql_client::GraphQLQuery;
 
-    //     client.reply_to_publication("").await.unwrap();
-    // }
+            #[derive(GraphQLQuery)]
+            #[graphql(
+                schema_path = "schema.graphql",
+                query_path = "src/test_support/queries.graphql"
+            )]
+            pub struct GetIdentityConfig;
+
+            #[derive(GraphQLQuery)]
+            #[graphql(
+                schema_path = "schema.graphql",
+                query_path = "src/test_support/queries.graphql"
+            )]
+            pub struct PostApplication;
+        }
+
+        let server = MockServer::start().await.unwrap();
+
+        let variables = || custom::get_identity_config::Variables {
+            realm: String::from("sshn"),
+        };
+
+        let response = server
+            .client()
+            .execute::<custom::GetIdentityConfig>(variables())
+            .await
+            .unwrap();
+
+        assert!(response.errors.is_empty());
+        assert_eq!(
+            response
+                .data
+                .identity_config
+                .unwrap()
+                .portal_client_id
+                .as_deref(),
+            Some("portal-legacy")
+        );
+
+        let client = server.login().await.unwrap();
+
+        client
+            .execute::<custom::GetIdentityConfig>(variables())
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+
+        assert!(requests
+            .last()
+            .and_then(|request| request.header("authorization"))
+            .is_some_and(|value| value.starts_with("Bearer ")));
+
+        // Partial data is returned together with the errors explaining it.
+        let response = client
+            .execute::<custom::PostApplication>(custom::post_application::Variables {
+                publication_id: UNKNOWN_PUBLICATION_ID.to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(response.data.housing_apply_to_unit.is_none());
+        assert_eq!(response.errors[0].message, "Publication not found");
+    }
 }
@@ -1,5 +1,4 @@
 use graphql_client::GraphQLQuery;
-use serde::Deserialize;
 
 type Cursor = String;
 type Decimal = f64;
@@ -29,7 +28,10 @@ pub struct PostApplication;
 )]
 pub struct GetIdentityConfig;
 
-#[derive(Deserialize, Debug)]
-pub struct GraphqlResponse<T> {
-    pub data: T,
-}
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.graphql",
+    query_path = "queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct GetLoggedInUser;
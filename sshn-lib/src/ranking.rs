@@ -0,0 +1,323 @@
+use std::cmp::Ordering;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::{error::Result, publication::Publication};
+
+/// Rules to select and rank publications, e.g. to decide which publications to reply to.
+///
+/// Publications that do not satisfy the constraints are dropped,
+/// the rest are scored using the weights and bonuses and sorted from the highest to the lowest score.
+///
+/// ```toml
+/// [constraints]
+/// max_rent = 600.0
+/// cities = ["Nijmegen", "Arnhem"]
+///
+/// [[weights]]
+/// field = "higher_priority"
+/// weight = -1.0
+///
+/// [[bonuses]]
+/// is_match = true
+/// score = 100.0
+/// ```
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ranking {
+    pub constraints: Constraints,
+    pub weights: Vec<Weight>,
+    pub bonuses: Vec<Bonus>,
+}
+
+/// Hard constraints, publications that do not satisfy all of them are never selected.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Constraints {
+    pub min_rent: Option<f64>,
+    pub max_rent: Option<f64>,
+    pub max_applicants: Option<i64>,
+    pub max_higher_priority: Option<i64>,
+    /// Only select publications in one of these cities, any city is allowed when empty.
+    pub cities: Vec<String>,
+    /// Only select publications that are a 100% match with the applicant.
+    pub only_matches: bool,
+}
+
+/// A numeric property of a publication that can be used to score it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Rent,
+    Applicants,
+    HigherPriority,
+}
+
+impl Field {
+    fn value(&self, publication: &Publication) -> f64 {
+        match self {
+            Field::Rent => publication.rent(),
+            Field::Applicants => publication.nr_of_applicants() as f64,
+            Field::HigherPriority => publication.nr_of_people_with_higher_priority() as f64,
+        }
+    }
+}
+
+/// Adds the value of the field multiplied by the weight to the score.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Weight {
+    pub field: Field,
+    pub weight: f64,
+}
+
+/// Adds a fixed score to publications matching all of the given conditions.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bonus {
+    pub city: Option<String>,
+    pub is_match: Option<bool>,
+    pub score: f64,
+}
+
+impl Bonus {
+    fn applies(&self, publication: &Publication) -> bool {
+        if let Some(city) = self.city.as_ref() {
+            if !city.eq_ignore_ascii_case(publication.city()) {
+                return false;
+            }
+        }
+
+        if let Some(is_match) = self.is_match {
+            if is_match != publication.is_match() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A publication together with the score it was given.
+#[derive(Debug)]
+pub struct RankedPublication {
+    publication: Publication,
+    score: f64,
+}
+
+impl RankedPublication {
+    pub fn publication(&self) -> &Publication {
+        &self.publication
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+}
+
+impl From<RankedPublication> for Publication {
+    fn from(ranked: RankedPublication) -> Self {
+        ranked.publication
+    }
+}
+
+// Serialized as a flat struct, so it can also be written to formats like CSV.
+impl Serialize for RankedPublication {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let publication = &self.publication;
+
+        let mut state = serializer.serialize_struct("RankedPublication", 8)?;
+
+        state.serialize_field("id", publication.id())?;
+        state.serialize_field("name", publication.name())?;
+        state.serialize_field("city", publication.city())?;
+        state.serialize_field("nr_of_applicants", &publication.nr_of_applicants())?;
+        state.serialize_field(
+            "nr_of_people_with_higher_priority",
+            &publication.nr_of_people_with_higher_priority(),
+        )?;
+        state.serialize_field("is_match", &publication.is_match())?;
+        state.serialize_field("rent", &publication.rent())?;
+        state.serialize_field("score", &self.score)?;
+
+        state.end()
+    }
+}
+
+impl Ranking {
+    /// Parse rules from a TOML document.
+    pub fn from_toml<T: AsRef<str>>(rules: T) -> Result<Self> {
+        let ranking = toml::from_str(rules.as_ref())?;
+
+        Ok(ranking)
+    }
+
+    /// Rules that rank publications by the chance of getting them:
+    /// matches first, then the fewest people with a higher priority, then the fewest applicants.
+    pub fn by_chance() -> Self {
+        Self {
+            constraints: Constraints::default(),
+            weights: vec![
+                Weight {
+                    field: Field::HigherPriority,
+                    weight: -1.0,
+                },
+                Weight {
+                    field: Field::Applicants,
+                    weight: -0.01,
+                },
+            ],
+            bonuses: vec![Bonus {
+                city: None,
+                is_match: Some(true),
+                score: 1000.0,
+            }],
+        }
+    }
+
+    /// Whether the publication satisfies all constraints.
+    pub fn allows(&self, publication: &Publication) -> bool {
+        let constraints = &self.constraints;
+
+        if constraints
+            .min_rent
+            .is_some_and(|min_rent| publication.rent() < min_rent)
+        {
+            return false;
+        }
+
+        if constraints
+            .max_rent
+            .is_some_and(|max_rent| publication.rent() > max_rent)
+        {
+            return false;
+        }
+
+        if constraints
+            .max_applicants
+            .is_some_and(|max_applicants| publication.nr_of_applicants() > max_applicants)
+        {
+            return false;
+        }
+
+        if constraints
+            .max_higher_priority
+            .is_some_and(|max| publication.nr_of_people_with_higher_priority() > max)
+        {
+            return false;
+        }
+
+        if !constraints.cities.is_empty()
+            && !constraints
+                .cities
+                .iter()
+                .any(|city| city.eq_ignore_ascii_case(publication.city()))
+        {
+            return false;
+        }
+
+        if constraints.only_matches && !publication.is_match() {
+            return false;
+        }
+
+        true
+    }
+
+    pub fn score(&self, publication: &Publication) -> f64 {
+        let weighted: f64 = self
+            .weights
+            .iter()
+            .map(|weight| weight.field.value(publication) * weight.weight)
+            .sum();
+
+        let bonus: f64 = self
+            .bonuses
+            .iter()
+            .filter(|bonus| bonus.applies(publication))
+            .map(|bonus| bonus.score)
+            .sum();
+
+        weighted + bonus
+    }
+
+    /// Drop the publications that do not satisfy the constraints,
+    /// then sort the rest from the highest to the lowest score.
+    pub fn rank<P: IntoIterator<Item = Publication>>(
+        &self,
+        publications: P,
+    ) -> Vec<RankedPublication> {
+        let mut ranked: Vec<RankedPublication> = publications
+            .into_iter()
+            .filter(|publication| self.allows(publication))
+            .map(|publication| RankedPublication {
+                score: self.score(&publication),
+                publication,
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::PublicationBuilder;
+
+    #[test]
+    fn test_rank() {
+        let ranking = Ranking::from_toml(
+            r#"
+            [constraints]
+            max_rent = 600.0
+
+            [[weights]]
+            field = "higher_priority"
+            weight = -1.0
+
+            [[bonuses]]
+            city = "nijmegen"
+            score = 5.0
+            "#,
+        )
+        .unwrap();
+
+        let ranked = ranking.rank(vec![
+            PublicationBuilder::new("expensive").rent(700.0).build(),
+            PublicationBuilder::new("arnhem")
+                .city("Arnhem")
+                .higher_priority(1)
+                .build(),
+            PublicationBuilder::new("nijmegen")
+                .rent(500.0)
+                .higher_priority(3)
+                .is_match(false)
+                .build(),
+        ]);
+
+        let ids: Vec<&str> = ranked
+            .iter()
+            .map(|ranked| ranked.publication().id())
+            .collect();
+
+        assert_eq!(ids, ["nijmegen", "arnhem"]);
+        assert_eq!(ranked[0].score(), 2.0);
+    }
+
+    #[test]
+    fn test_by_chance_prefers_matches() {
+        let ranked = Ranking::by_chance().rank(vec![
+            PublicationBuilder::new("no-match").is_match(false).build(),
+            PublicationBuilder::new("match").higher_priority(20).build(),
+        ]);
+
+        assert_eq!(ranked[0].publication().id(), "match");
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Ranking::from_toml("[[weights]]\nfield = \"size\"\nweight = 1.0").is_err());
+    }
+}
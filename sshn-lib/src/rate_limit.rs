@@ -0,0 +1,126 @@
+use std::{sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+/// A token bucket limiting how many requests are sent to the SSHN API.
+/// Share it between clients using an `Arc`, so they are limited together.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Default for RateLimiter {
+    /// Allows bursts of 5 requests, refilling at 2 requests per second.
+    fn default() -> Self {
+        Self::new(5, 2.0)
+    }
+}
+
+impl RateLimiter {
+    /// Allow bursts of `capacity` requests, refilling at `per_second` requests per second.
+    /// A rate that is not a positive, finite number disables the limit.
+    pub fn new(capacity: u32, per_second: f64) -> Self {
+        let capacity = f64::from(capacity.max(1));
+
+        let per_second = if per_second.is_finite() && per_second > 0.0 {
+            per_second
+        } else {
+            f64::INFINITY
+        };
+
+        Self {
+            capacity,
+            per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// How many requests may be sent at once.
+    pub fn capacity(&self) -> u32 {
+        self.capacity as u32
+    }
+
+    /// How many requests may be sent per second on average.
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Wait until a request may be sent.
+    pub async fn acquire(&self) {
+        if self.per_second.is_infinite() {
+            return;
+        }
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+
+                bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.capacity);
+                bucket.updated = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+
+                    return;
+                }
+
+                // Very low rates can exceed the longest duration, those wait forever.
+                Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+                    .unwrap_or(Duration::MAX)
+            };
+
+            log::debug!("Rate limited, waiting {:?} before sending request", wait);
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_waits_when_empty() {
+        let limiter = RateLimiter::new(2, 20.0);
+
+        let start = Instant::now();
+
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+
+        // Two requests are allowed immediately, the other two wait 50ms each.
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rate_disables_limit() {
+        for per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limiter = RateLimiter::new(1, per_second);
+
+            let acquired = tokio::time::timeout(Duration::from_secs(1), async {
+                for _ in 0..3 {
+                    limiter.acquire().await;
+                }
+            })
+            .await;
+
+            assert!(acquired.is_ok());
+        }
+    }
+}
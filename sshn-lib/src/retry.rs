@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+
+/// When and how long to wait before retrying requests that failed because of
+/// rate limiting (429), server errors (5xx) or network errors.
+/// Requests that are not idempotent, like mutations and token grants, are only retried
+/// when they were certainly not processed: when rate limited or when the connection failed.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry failed requests.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    pub fn max_retries(self, max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..self
+        }
+    }
+
+    /// Set the backoff before the first retry, it doubles with every following retry.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        Self {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Set the longest time to wait before a retry.
+    /// Requests are not retried when the server asks to wait longer than this.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        Self {
+            max_backoff,
+            ..self
+        }
+    }
+
+    pub(crate) fn should_retry_status(status: StatusCode, idempotent: bool) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || idempotent && Self::is_server_error(status)
+    }
+
+    fn is_server_error(status: StatusCode) -> bool {
+        status == StatusCode::INTERNAL_SERVER_ERROR
+            || status == StatusCode::BAD_GATEWAY
+            || status == StatusCode::SERVICE_UNAVAILABLE
+            || status == StatusCode::GATEWAY_TIMEOUT
+    }
+
+    pub(crate) fn should_retry_error(error: &reqwest::Error, idempotent: bool) -> bool {
+        error.is_connect() || idempotent && (error.is_timeout() || error.is_request())
+    }
+
+    /// How long to wait before the given retry, starting at 0, or `None` if the request should not be retried.
+    /// Uses exponential backoff with full jitter, unless the server said how long to wait.
+    pub(crate) fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if retry >= self.max_retries {
+            return None;
+        }
+
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_backoff).then_some(retry_after);
+        }
+
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+
+        let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+
+        Some(backoff.mul_f64(jitter))
+    }
+}
+
+/// Parse the `Retry-After` header, which is either an amount of seconds or a date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::default()
+            .max_retries(2)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(10));
+
+        assert!(policy.delay(1, None).unwrap() <= Duration::from_secs(2));
+        assert_eq!(policy.delay(2, None), None);
+
+        assert_eq!(
+            policy.delay(0, Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let response: Response = http::Response::builder()
+            .status(429)
+            .header(RETRY_AFTER, "7")
+            .body("")
+            .unwrap()
+            .into();
+
+        assert_eq!(retry_after(&response), Some(Duration::from_secs(7)));
+
+        let response: Response = http::Response::builder()
+            .status(503)
+            .header(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT")
+            .body("")
+            .unwrap()
+            .into();
+
+        assert_eq!(retry_after(&response), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,505 @@
+//! A local stand-in for the SSHN API and its identity provider, serving recorded responses,
+//! so the clients can be tested without network access or an SSHN account.
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+};
+
+use base64::prelude::*;
+use chrono::Utc;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    client::{AuthenticatedClient, LoginType, UnAuthenticatedClient},
+    error::Result,
+    publication::Publication,
+};
+
+/// The username accepted by the mock identity provider.
+pub const USERNAME: &str = "test@example.org";
+
+/// The password accepted by the mock identity provider.
+pub const PASSWORD: &str = "password";
+
+/// The id of the first publication in the recorded publications list.
+pub const PUBLICATION_ID: &str = "3e6c1b0e-6b7e-4f1f-9a51-4c1b6f2a7d01";
+
+/// A publication id the mock API does not know, replying to it returns a GraphQL error.
+pub const UNKNOWN_PUBLICATION_ID: &str = "unknown";
+
+/// A publication for which the mock API accepts replies without returning their state.
+pub const STATELESS_PUBLICATION_ID: &str = "stateless";
+
+/// Lifetime in seconds of the access tokens issued by the mock identity provider.
+pub const ACCESS_TOKEN_LIFETIME: i64 = 300;
+
+/// Lifetime in seconds of the refresh tokens issued by the mock identity provider.
+pub const REFRESH_TOKEN_LIFETIME: i64 = 1800;
+
+const GET_PUBLICATIONS_LIST: &str = include_str!("fixtures/get_publications_list.json");
+const POST_APPLICATION: &str = include_str!("fixtures/post_application.json");
+const GET_IDENTITY_CONFIG: &str = include_str!("fixtures/get_identity_config.json");
+const GET_LOGGED_IN_USER: &str = include_str!("fixtures/get_logged_in_user.json");
+
+/// A request received by the mock server.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header<N: AsRef<str>>(&self, name: N) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name.as_ref()))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The name of the GraphQL operation, if this is a GraphQL request.
+    pub fn operation_name(&self) -> Option<String> {
+        let body: Value = serde_json::from_str(&self.body).ok()?;
+
+        body.get("operationName")?.as_str().map(String::from)
+    }
+
+    /// A variable of the GraphQL operation, if this is a GraphQL request.
+    pub fn variable<N: AsRef<str>>(&self, name: N) -> Option<Value> {
+        let body: Value = serde_json::from_str(&self.body).ok()?;
+
+        body.get("variables")?.get(name.as_ref()).cloned()
+    }
+}
+
+/// Builds publications for tests, every field that is not set has a plausible default.
+#[derive(Debug, Clone)]
+pub struct PublicationBuilder {
+    id: String,
+    city: String,
+    rent: f64,
+    higher_priority: i64,
+    is_match: bool,
+}
+
+impl PublicationBuilder {
+    pub fn new<I: Into<String>>(id: I) -> Self {
+        Self {
+            id: id.into(),
+            city: String::from("Nijmegen"),
+            rent: 400.0,
+            higher_priority: 0,
+            is_match: true,
+        }
+    }
+
+    pub fn city<C: Into<String>>(self, city: C) -> Self {
+        Self {
+            city: city.into(),
+            ..self
+        }
+    }
+
+    pub fn rent(self, rent: f64) -> Self {
+        Self { rent, ..self }
+    }
+
+    /// The number of people with a higher priority than the user.
+    pub fn higher_priority(self, higher_priority: i64) -> Self {
+        Self {
+            higher_priority,
+            ..self
+        }
+    }
+
+    pub fn is_match(self, is_match: bool) -> Self {
+        Self { is_match, ..self }
+    }
+
+    pub fn build(self) -> Publication {
+        serde_json::from_value(json!({
+            "id": self.id,
+            "name": "Room",
+            "city": self.city,
+            "nr_of_applicants": 10,
+            "nr_of_people_with_higher_priority": self.higher_priority,
+            "is_match": self.is_match,
+            "rent": self.rent,
+        }))
+        .unwrap()
+    }
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    fn fixture(fixture: &str) -> Self {
+        Self {
+            status: 200,
+            body: fixture.to_string(),
+        }
+    }
+}
+
+/// A mock of the SSHN GraphQL API and identity provider, running on a random local port.
+/// Requests to other paths are answered with an empty JSON object.
+/// The server is stopped when it is dropped.
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+    failures: Arc<Mutex<Vec<u16>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let failures = Arc::new(Mutex::new(Vec::new()));
+
+        let task = {
+            let requests = Arc::clone(&requests);
+            let failures = Arc::clone(&failures);
+
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let requests = Arc::clone(&requests);
+                    let failures = Arc::clone(&failures);
+
+                    tokio::spawn(async move {
+                        if let Err(error) = handle_connection(stream, requests, failures).await {
+                            log::warn!("Mock server failed to handle request: {}", error);
+                        }
+                    });
+                }
+            })
+        };
+
+        Ok(Self {
+            url,
+            requests,
+            failures,
+            task,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn graphql_url(&self) -> String {
+        format!("{}/graphql", self.url)
+    }
+
+    pub fn auth_url(&self) -> String {
+        format!("{}/auth", self.url)
+    }
+
+    /// A client that talks to this server instead of the SSHN API.
+    pub fn client(&self) -> UnAuthenticatedClient {
+        UnAuthenticatedClient::new(Some(self.graphql_url())).auth_url(self.auth_url())
+    }
+
+    /// A client that is logged in to this server as [`USERNAME`].
+    pub async fn login(&self) -> Result<AuthenticatedClient> {
+        self.client()
+            .login(LoginType::Password {
+                username: USERNAME.to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+    }
+
+    /// Answer the next requests with the given statuses, in order, before answering normally again.
+    /// Rate limited (429) responses ask to retry after 0 seconds.
+    pub fn fail_next<S: IntoIterator<Item = u16>>(&self, statuses: S) {
+        self.failures.lock().unwrap().extend(statuses);
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    requests: Arc<Mutex<Vec<Request>>>,
+    failures: Arc<Mutex<Vec<u16>>>,
+) -> io::Result<()> {
+    let request = read_request(&mut stream).await?;
+
+    let failure = {
+        let mut failures = failures.lock().unwrap();
+
+        (!failures.is_empty()).then(|| failures.remove(0))
+    };
+
+    let response = match failure {
+        Some(status) => Response::json(status, json!({ "error": reason(status) })),
+        None => respond(&request),
+    };
+
+    requests.lock().unwrap().push(request);
+
+    let retry_after = if response.status == 429 {
+        "retry-after: 0\r\n"
+    } else {
+        ""
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{}connection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        retry_after
+    );
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+
+    let head_end = loop {
+        let read = stream.read(&mut buffer).await?;
+
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        data.extend_from_slice(&buffer[..read]);
+
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+
+    let mut lines = head.lines();
+
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let body_start = head_end + 4;
+
+    while data.len() < body_start + content_length {
+        let read = stream.read(&mut buffer).await?;
+
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        data.extend_from_slice(&buffer[..read]);
+    }
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&data[body_start..body_start + content_length]).to_string(),
+    })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Not Found",
+    }
+}
+
+fn respond(request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/graphql") => respond_graphql(request),
+        ("POST", "/auth/token") => respond_token(request),
+        ("POST", "/auth/logout") => Response {
+            status: 204,
+            body: String::new(),
+        },
+        // Any other request is accepted, so the server can also stand in for other services, e.g. webhooks.
+        _ => Response::json(200, json!({})),
+    }
+}
+
+fn respond_graphql(request: &Request) -> Response {
+    let authenticated = request
+        .header("authorization")
+        .is_some_and(|value| value.starts_with("Bearer "));
+
+    match request.operation_name().as_deref() {
+        Some("GetPublicationsList") => respond_publications_page(request),
+        Some("GetIdentityConfig") => Response::fixture(GET_IDENTITY_CONFIG),
+        Some("PostApplication" | "GetLoggedInUser") if !authenticated => {
+            Response::json(401, json!({ "errors": [{ "message": "Unauthorized" }] }))
+        }
+        Some("PostApplication")
+            if request.variable("publicationId") == Some(json!(UNKNOWN_PUBLICATION_ID)) =>
+        {
+            Response::json(
+                200,
+                json!({
+                    "data": { "housingApplyToUnit": null },
+                    "errors": [{
+                        "message": "Publication not found",
+                        "path": ["housingApplyToUnit"],
+                        "extensions": { "code": "NOT_FOUND" },
+                    }],
+                }),
+            )
+        }
+        Some("PostApplication")
+            if request.variable("publicationId") == Some(json!(STATELESS_PUBLICATION_ID)) =>
+        {
+            Response::json(200, json!({ "data": { "housingApplyToUnit": null } }))
+        }
+        Some("PostApplication") => Response::fixture(POST_APPLICATION),
+        Some("GetLoggedInUser") => Response::fixture(GET_LOGGED_IN_USER),
+        _ => Response::json(
+            400,
+            json!({ "errors": [{ "message": "Unknown operation" }] }),
+        ),
+    }
+}
+
+/// The recorded publications, paged using the `first` and `after` variables.
+fn respond_publications_page(request: &Request) -> Response {
+    let mut response: Value = serde_json::from_str(GET_PUBLICATIONS_LIST).unwrap();
+    let nodes = &mut response["data"]["housingPublications"]["nodes"];
+
+    let edges = nodes["edges"].as_array().cloned().unwrap_or_default();
+
+    let start = match request.variable("after") {
+        Some(Value::String(after)) => edges
+            .iter()
+            .position(|edge| edge["cursor"] == after)
+            .map_or(edges.len(), |index| index + 1),
+        _ => 0,
+    };
+
+    let first = request
+        .variable("first")
+        .and_then(|first| first.as_u64())
+        .map_or(edges.len(), |first| first as usize);
+
+    let page: Vec<Value> = edges.iter().skip(start).take(first).cloned().collect();
+
+    nodes["pageInfo"]["hasNextPage"] = json!(start + page.len() < edges.len());
+
+    if let Some(edge) = page.last() {
+        nodes["pageInfo"]["endCursor"] = edge["cursor"].clone();
+    }
+
+    nodes["edges"] = json!(page);
+
+    Response::json(200, response)
+}
+
+fn respond_token(request: &Request) -> Response {
+    let params: HashMap<String, String> =
+        serde_urlencoded::from_str(&request.body).unwrap_or_default();
+
+    let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+
+    let valid = match param("grant_type") {
+        "password" => param("username") == USERNAME && param("password") == PASSWORD,
+        "authorization_code" => !param("code").is_empty(),
+        "refresh_token" => !param("refresh_token").is_empty(),
+        _ => false,
+    };
+
+    if !valid {
+        return Response::json(
+            401,
+            json!({
+                "error": "invalid_grant",
+                "error_description": "Invalid user credentials",
+            }),
+        );
+    }
+
+    Response::json(
+        200,
+        json!({
+            "access_token": issue_token(ACCESS_TOKEN_LIFETIME),
+            "expires_in": ACCESS_TOKEN_LIFETIME,
+            "refresh_expires_in": REFRESH_TOKEN_LIFETIME,
+            "refresh_token": issue_token(REFRESH_TOKEN_LIFETIME),
+            "token_type": "Bearer",
+            "id_token": issue_token(ACCESS_TOKEN_LIFETIME),
+            "session_state": "mock-session",
+        }),
+    )
+}
+
+/// An unsigned JWT for the mock user, valid for the given amount of seconds.
+fn issue_token(lifetime: i64) -> String {
+    let header = json!({ "alg": "none", "typ": "JWT" });
+
+    let claims = json!({
+        "sub": "mock-user",
+        "email": USERNAME,
+        "name": "Test van User",
+        "preferred_username": USERNAME,
+        "exp": Utc::now().timestamp() + lifetime,
+        "realm_access": { "roles": ["user"] },
+    });
+
+    format!(
+        "{}.{}.",
+        BASE64_URL_SAFE_NO_PAD.encode(header.to_string()),
+        BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+    )
+}
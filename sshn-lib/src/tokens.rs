@@ -1,31 +1,44 @@
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::{claims::Claims, error::Result};
+
 #[derive(Deserialize, Debug)]
 pub struct LoginResponse {
     pub access_token: String,
     pub expires_in: i64,
     pub refresh_expires_in: i64,
     pub refresh_token: String,
-    // token_type: String,
-    // id_token: String,
-    // session_state: String,
+    pub token_type: Option<String>,
+    pub id_token: Option<String>,
+    pub session_state: Option<String>,
+}
+
+/// Get the expiry date of a token from its `exp` claim, falling back to the given lifetime.
+fn expiry_of(token: &str, expires_in: i64) -> DateTime<Utc> {
+    Claims::decode(token)
+        .ok()
+        .and_then(|claims| claims.expires())
+        .unwrap_or_else(|| Utc::now() + Duration::seconds(expires_in))
 }
 
-impl Into<Tokens> for LoginResponse {
-    fn into(self) -> Tokens {
-        Tokens::new(
-            Token::new(
-                self.refresh_token,
-                Utc::now() + Duration::seconds(self.refresh_expires_in),
-                TokenType::Refresh,
-            ),
-            Token::new(
-                self.access_token,
-                Utc::now() + Duration::seconds(self.expires_in),
-                TokenType::Access,
-            ),
-        )
+impl From<LoginResponse> for Tokens {
+    fn from(response: LoginResponse) -> Self {
+        let refresh_expires = expiry_of(&response.refresh_token, response.refresh_expires_in);
+        let access_expires = expiry_of(&response.access_token, response.expires_in);
+
+        let id_token = response.id_token.map(|id_token| {
+            let expires = expiry_of(&id_token, response.expires_in);
+
+            Token::new(id_token, expires, TokenType::Id)
+        });
+
+        Tokens {
+            refresh_token: Token::new(response.refresh_token, refresh_expires, TokenType::Refresh),
+            access_token: Token::new(response.access_token, access_expires, TokenType::Access),
+            id_token,
+            session_state: response.session_state,
+        }
     }
 }
 
@@ -41,6 +54,7 @@ pub enum TokenType {
     #[default]
     Access,
     Refresh,
+    Id,
 }
 
 impl Default for Token {
@@ -76,15 +90,29 @@ impl Token {
         self.expires
     }
 
+    /// Decode the claims contained in this token, without verifying its signature.
+    pub fn claims(&self) -> Result<Claims> {
+        Claims::decode(&self.content)
+    }
+
     pub fn has_expired(&self) -> bool {
         self.expires <= Utc::now()
     }
+
+    /// Whether the token has expired or will expire within the given margin.
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        self.expires - margin <= Utc::now()
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Tokens {
     refresh_token: Token,
     access_token: Token,
+    #[serde(default)]
+    id_token: Option<Token>,
+    #[serde(default)]
+    session_state: Option<String>,
 }
 
 impl Tokens {
@@ -92,6 +120,8 @@ impl Tokens {
         Self {
             refresh_token,
             access_token,
+            id_token: None,
+            session_state: None,
         }
     }
 
@@ -102,4 +132,20 @@ impl Tokens {
     pub fn access_token(&self) -> &Token {
         &self.access_token
     }
+
+    pub fn id_token(&self) -> Option<&Token> {
+        self.id_token.as_ref()
+    }
+
+    pub fn session_state(&self) -> Option<&str> {
+        self.session_state.as_deref()
+    }
+
+    /// The claims describing the logged in user, taken from the ID token if available.
+    pub fn claims(&self) -> Result<Claims> {
+        self.id_token
+            .as_ref()
+            .unwrap_or(&self.access_token)
+            .claims()
+    }
 }
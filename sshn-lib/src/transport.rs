@@ -0,0 +1,48 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Method, Request, Response, StatusCode, Url};
+
+use crate::error::{Error, Result};
+
+/// Sends HTTP requests on behalf of the clients.
+/// Implemented for `reqwest::Client`, implement it yourself to wrap requests in middleware.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn execute(&self, request: Request) -> Result<Response>;
+}
+
+#[async_trait]
+impl Transport for reqwest::Client {
+    async fn execute(&self, request: Request) -> Result<Response> {
+        Ok(reqwest::Client::execute(self, request).await?)
+    }
+}
+
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    async fn execute(&self, request: Request) -> Result<Response> {
+        (**self).execute(request).await
+    }
+}
+
+/// A request that was sent, passed to the response hooks once it completed or failed.
+#[derive(Debug)]
+pub struct Exchange<'a> {
+    pub method: &'a Method,
+    pub url: &'a Url,
+    /// The status of the response, or `None` if no response was received.
+    pub status: Option<StatusCode>,
+    /// The error, if no response was received.
+    pub error: Option<&'a Error>,
+    /// How long it took to receive the response, excluding the time spent rate limited.
+    pub elapsed: Duration,
+    /// The attempt this was, starting at 0 and increasing with every retry.
+    pub attempt: u32,
+}
+
+/// Called with every request right before it is sent, for every attempt. It may add headers.
+pub type RequestHook = Arc<dyn Fn(&mut Request) + Send + Sync>;
+
+/// Called after every attempt at a request, with the outcome of that attempt.
+pub type ResponseHook = Arc<dyn Fn(&Exchange) + Send + Sync>;
@@ -0,0 +1,642 @@
+//! Finding the changes between two versions of the schema that break the operations in `queries.graphql`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use graphql_parser::{
+    query::{self, OperationDefinition, Selection, SelectionSet, TypeCondition},
+    schema::{self, Type, TypeDefinition},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Kind::Scalar => "scalar",
+            Kind::Object => "object",
+            Kind::Interface => "interface",
+            Kind::Union => "union",
+            Kind::Enum => "enum",
+            Kind::InputObject => "input object",
+        };
+
+        write!(f, "{}", kind)
+    }
+}
+
+type ValueType = Type<'static, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct InputDef {
+    value_type: ValueType,
+    named_type: String,
+    required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FieldDef {
+    field_type: ValueType,
+    named_type: String,
+    arguments: BTreeMap<String, InputDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TypeDef {
+    kind: Kind,
+    fields: BTreeMap<String, FieldDef>,
+    input_fields: BTreeMap<String, InputDef>,
+    values: BTreeSet<String>,
+}
+
+/// The parts of a schema that operations can depend on.
+#[derive(Debug, PartialEq)]
+pub struct Schema {
+    query: String,
+    mutation: String,
+    subscription: String,
+    types: BTreeMap<String, TypeDef>,
+}
+
+fn named_type(value_type: &Type<'_, String>) -> String {
+    match value_type {
+        Type::NamedType(name) => name.clone(),
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+fn to_static(value_type: &Type<'_, String>) -> ValueType {
+    match value_type {
+        Type::NamedType(name) => Type::NamedType(name.clone()),
+        Type::ListType(inner) => Type::ListType(Box::new(to_static(inner))),
+        Type::NonNullType(inner) => Type::NonNullType(Box::new(to_static(inner))),
+    }
+}
+
+/// Whether results of the new type can still be read as the old type, which holds when fields become non-null.
+fn is_safe_output_change(old: &ValueType, new: &ValueType) -> bool {
+    match (old, new) {
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_output_change(old, new),
+        (old, Type::NonNullType(new)) => is_safe_output_change(old, new),
+        (Type::ListType(old), Type::ListType(new)) => is_safe_output_change(old, new),
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        _ => false,
+    }
+}
+
+/// Whether values of the old type are still accepted by the new type, which holds when inputs become optional.
+fn is_safe_input_change(old: &ValueType, new: &ValueType) -> bool {
+    match (old, new) {
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_input_change(old, new),
+        (Type::NonNullType(old), new) => is_safe_input_change(old, new),
+        (Type::ListType(old), Type::ListType(new)) => is_safe_input_change(old, new),
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        _ => false,
+    }
+}
+
+fn input_def(input_value: &schema::InputValue<'_, String>) -> InputDef {
+    InputDef {
+        value_type: to_static(&input_value.value_type),
+        named_type: named_type(&input_value.value_type),
+        required: matches!(input_value.value_type, Type::NonNullType(_))
+            && input_value.default_value.is_none(),
+    }
+}
+
+fn field_defs(fields: &[schema::Field<'_, String>]) -> BTreeMap<String, FieldDef> {
+    fields
+        .iter()
+        .map(|field| {
+            let field_def = FieldDef {
+                field_type: to_static(&field.field_type),
+                named_type: named_type(&field.field_type),
+                arguments: field
+                    .arguments
+                    .iter()
+                    .map(|argument| (argument.name.clone(), input_def(argument)))
+                    .collect(),
+            };
+
+            (field.name.clone(), field_def)
+        })
+        .collect()
+}
+
+impl TypeDef {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            fields: BTreeMap::new(),
+            input_fields: BTreeMap::new(),
+            values: BTreeSet::new(),
+        }
+    }
+}
+
+impl From<&schema::Document<'_, String>> for Schema {
+    fn from(document: &schema::Document<'_, String>) -> Self {
+        let mut schema = Schema {
+            query: String::from("Query"),
+            mutation: String::from("Mutation"),
+            subscription: String::from("Subscription"),
+            types: BTreeMap::new(),
+        };
+
+        for definition in document.definitions.iter() {
+            let type_definition = match definition {
+                schema::Definition::SchemaDefinition(definition) => {
+                    if let Some(query) = &definition.query {
+                        schema.query = query.clone();
+                    }
+                    if let Some(mutation) = &definition.mutation {
+                        schema.mutation = mutation.clone();
+                    }
+                    if let Some(subscription) = &definition.subscription {
+                        schema.subscription = subscription.clone();
+                    }
+
+                    continue;
+                }
+                schema::Definition::TypeDefinition(type_definition) => type_definition,
+                _ => continue,
+            };
+
+            let (name, type_def) = match type_definition {
+                TypeDefinition::Scalar(scalar) => (&scalar.name, TypeDef::new(Kind::Scalar)),
+                TypeDefinition::Object(object) => (
+                    &object.name,
+                    TypeDef {
+                        fields: field_defs(&object.fields),
+                        ..TypeDef::new(Kind::Object)
+                    },
+                ),
+                TypeDefinition::Interface(interface) => (
+                    &interface.name,
+                    TypeDef {
+                        fields: field_defs(&interface.fields),
+                        ..TypeDef::new(Kind::Interface)
+                    },
+                ),
+                TypeDefinition::Union(union) => (&union.name, TypeDef::new(Kind::Union)),
+                TypeDefinition::Enum(enum_type) => (
+                    &enum_type.name,
+                    TypeDef {
+                        values: enum_type
+                            .values
+                            .iter()
+                            .map(|value| value.name.clone())
+                            .collect(),
+                        ..TypeDef::new(Kind::Enum)
+                    },
+                ),
+                TypeDefinition::InputObject(input) => (
+                    &input.name,
+                    TypeDef {
+                        input_fields: input
+                            .fields
+                            .iter()
+                            .map(|field| (field.name.clone(), input_def(field)))
+                            .collect(),
+                        ..TypeDef::new(Kind::InputObject)
+                    },
+                ),
+            };
+
+            schema.types.insert(name.clone(), type_def);
+        }
+
+        schema
+    }
+}
+
+/// The types, fields and arguments of a schema that are used by a set of operations.
+#[derive(Debug, Default)]
+pub struct Usage {
+    /// The arguments used per field, keyed by the type and field name.
+    fields: BTreeMap<(String, String), BTreeSet<String>>,
+    /// Enums and input objects used as arguments, variables or results.
+    types: BTreeSet<String>,
+}
+
+impl Usage {
+    /// Find everything used by the operations in the given document, resolved against the schema.
+    pub fn of<'d>(schema: &Schema, document: &'d query::Document<'d, String>) -> Self {
+        let mut usage = Usage::default();
+
+        let fragments: BTreeMap<&str, &query::FragmentDefinition<'_, String>> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                query::Definition::Fragment(fragment) => Some((fragment.name.as_str(), fragment)),
+                _ => None,
+            })
+            .collect();
+
+        let mut walker = Walker {
+            schema,
+            fragments,
+            visited: BTreeSet::new(),
+            usage: &mut usage,
+        };
+
+        for definition in document.definitions.iter() {
+            let query::Definition::Operation(operation) = definition else {
+                continue;
+            };
+
+            let (root, variables, selection_set) = match operation {
+                OperationDefinition::SelectionSet(selection_set) => {
+                    (&schema.query, &[][..], selection_set)
+                }
+                OperationDefinition::Query(query) => (
+                    &schema.query,
+                    &query.variable_definitions[..],
+                    &query.selection_set,
+                ),
+                OperationDefinition::Mutation(mutation) => (
+                    &schema.mutation,
+                    &mutation.variable_definitions[..],
+                    &mutation.selection_set,
+                ),
+                OperationDefinition::Subscription(subscription) => (
+                    &schema.subscription,
+                    &subscription.variable_definitions[..],
+                    &subscription.selection_set,
+                ),
+            };
+
+            for variable in variables {
+                walker.use_input(&named_type(&variable.var_type));
+            }
+
+            walker.walk(root, selection_set);
+        }
+
+        usage
+    }
+}
+
+struct Walker<'a, 'd> {
+    schema: &'a Schema,
+    fragments: BTreeMap<&'d str, &'d query::FragmentDefinition<'d, String>>,
+    /// Fragments that were already walked, so recursive fragments do not loop forever.
+    visited: BTreeSet<&'d str>,
+    usage: &'a mut Usage,
+}
+
+impl<'d> Walker<'_, 'd> {
+    fn walk(&mut self, type_name: &str, selection_set: &'d SelectionSet<'d, String>) {
+        for selection in selection_set.items.iter() {
+            match selection {
+                Selection::Field(field) if field.name.starts_with("__") => {}
+                Selection::Field(field) => {
+                    self.usage
+                        .fields
+                        .entry((type_name.to_string(), field.name.clone()))
+                        .or_default()
+                        .extend(field.arguments.iter().map(|(name, _)| name.clone()));
+
+                    let Some(field_def) = self
+                        .schema
+                        .types
+                        .get(type_name)
+                        .and_then(|type_def| type_def.fields.get(&field.name))
+                    else {
+                        continue;
+                    };
+
+                    for (name, _) in field.arguments.iter() {
+                        if let Some(argument) = field_def.arguments.get(name) {
+                            self.use_input(&argument.named_type);
+                        }
+                    }
+
+                    self.use_input(&field_def.named_type);
+
+                    self.walk(&field_def.named_type, &field.selection_set);
+                }
+                Selection::FragmentSpread(spread) => {
+                    let Some(fragment) = self.fragments.get(spread.fragment_name.as_str()) else {
+                        continue;
+                    };
+
+                    if !self.visited.insert(fragment.name.as_str()) {
+                        continue;
+                    }
+
+                    let TypeCondition::On(condition) = &fragment.type_condition;
+
+                    self.walk(condition, &fragment.selection_set);
+                }
+                Selection::InlineFragment(fragment) => match &fragment.type_condition {
+                    Some(TypeCondition::On(condition)) => {
+                        self.walk(condition, &fragment.selection_set)
+                    }
+                    None => self.walk(type_name, &fragment.selection_set),
+                },
+            }
+        }
+    }
+
+    /// Mark an enum or input object as used, including the types of all fields of input objects.
+    fn use_input(&mut self, type_name: &str) {
+        let Some(type_def) = self.schema.types.get(type_name) else {
+            return;
+        };
+
+        if !matches!(type_def.kind, Kind::Enum | Kind::InputObject) {
+            return;
+        }
+
+        if !self.usage.types.insert(type_name.to_string()) {
+            return;
+        }
+
+        for field in type_def.input_fields.values() {
+            self.use_input(&field.named_type);
+        }
+    }
+}
+
+/// A change to the schema that breaks an operation using the changed part.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    TypeRemoved(String),
+    KindChanged(String, String, String),
+    FieldRemoved(String, String),
+    FieldTypeChanged(String, String, String, String),
+    ArgumentRemoved(String, String, String),
+    ArgumentTypeChanged(String, String, String, String, String),
+    RequiredArgumentAdded(String, String, String),
+    EnumValueRemoved(String, String),
+    InputFieldRemoved(String, String),
+    InputFieldTypeChanged(String, String, String, String),
+    RequiredInputFieldAdded(String, String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::TypeRemoved(name) => write!(f, "Type {} was removed", name),
+            Change::KindChanged(name, old, new) => {
+                write!(f, "Type {} changed from {} to {}", name, old, new)
+            }
+            Change::FieldRemoved(type_name, field) => {
+                write!(f, "Field {}.{} was removed", type_name, field)
+            }
+            Change::FieldTypeChanged(type_name, field, old, new) => write!(
+                f,
+                "Field {}.{} changed type from {} to {}",
+                type_name, field, old, new
+            ),
+            Change::ArgumentRemoved(type_name, field, argument) => write!(
+                f,
+                "Argument {} of {}.{} was removed",
+                argument, type_name, field
+            ),
+            Change::ArgumentTypeChanged(type_name, field, argument, old, new) => write!(
+                f,
+                "Argument {} of {}.{} changed type from {} to {}",
+                argument, type_name, field, old, new
+            ),
+            Change::RequiredArgumentAdded(type_name, field, argument) => write!(
+                f,
+                "Required argument {} was added to {}.{}",
+                argument, type_name, field
+            ),
+            Change::EnumValueRemoved(name, value) => {
+                write!(f, "Value {} of enum {} was removed", value, name)
+            }
+            Change::InputFieldRemoved(name, field) => {
+                write!(f, "Input field {}.{} was removed", name, field)
+            }
+            Change::InputFieldTypeChanged(name, field, old, new) => write!(
+                f,
+                "Input field {}.{} changed type from {} to {}",
+                name, field, old, new
+            ),
+            Change::RequiredInputFieldAdded(name, field) => {
+                write!(f, "Required input field {}.{} was added", name, field)
+            }
+        }
+    }
+}
+
+/// Find the changes from the old to the new schema that break the given usage of the old schema.
+pub fn breaking_changes(old: &Schema, new: &Schema, usage: &Usage) -> BTreeSet<Change> {
+    let mut changes = BTreeSet::new();
+
+    // Returns the definitions of a used type in both schemas, if it was not removed or changed kind.
+    let both = |changes: &mut BTreeSet<Change>, name: &str| {
+        let old_def = old.types.get(name)?;
+
+        let Some(new_def) = new.types.get(name) else {
+            changes.insert(Change::TypeRemoved(name.to_string()));
+            return None;
+        };
+
+        if old_def.kind != new_def.kind {
+            changes.insert(Change::KindChanged(
+                name.to_string(),
+                old_def.kind.to_string(),
+                new_def.kind.to_string(),
+            ));
+            return None;
+        }
+
+        Some((old_def, new_def))
+    };
+
+    for ((type_name, field), arguments) in usage.fields.iter() {
+        let Some((old_def, new_def)) = both(&mut changes, type_name) else {
+            continue;
+        };
+
+        let Some(old_field) = old_def.fields.get(field) else {
+            continue;
+        };
+
+        let Some(new_field) = new_def.fields.get(field) else {
+            changes.insert(Change::FieldRemoved(type_name.clone(), field.clone()));
+            continue;
+        };
+
+        if !is_safe_output_change(&old_field.field_type, &new_field.field_type) {
+            changes.insert(Change::FieldTypeChanged(
+                type_name.clone(),
+                field.clone(),
+                old_field.field_type.to_string(),
+                new_field.field_type.to_string(),
+            ));
+        }
+
+        for argument in arguments {
+            let Some(old_argument) = old_field.arguments.get(argument) else {
+                continue;
+            };
+
+            match new_field.arguments.get(argument) {
+                None => {
+                    changes.insert(Change::ArgumentRemoved(
+                        type_name.clone(),
+                        field.clone(),
+                        argument.clone(),
+                    ));
+                }
+                Some(new_argument)
+                    if !is_safe_input_change(
+                        &old_argument.value_type,
+                        &new_argument.value_type,
+                    ) =>
+                {
+                    changes.insert(Change::ArgumentTypeChanged(
+                        type_name.clone(),
+                        field.clone(),
+                        argument.clone(),
+                        old_argument.value_type.to_string(),
+                        new_argument.value_type.to_string(),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (argument, new_argument) in new_field.arguments.iter() {
+            if new_argument.required && !arguments.contains(argument) {
+                changes.insert(Change::RequiredArgumentAdded(
+                    type_name.clone(),
+                    field.clone(),
+                    argument.clone(),
+                ));
+            }
+        }
+    }
+
+    for name in usage.types.iter() {
+        let Some((old_def, new_def)) = both(&mut changes, name) else {
+            continue;
+        };
+
+        for value in old_def.values.difference(&new_def.values) {
+            changes.insert(Change::EnumValueRemoved(name.clone(), value.clone()));
+        }
+
+        for (field, old_field) in old_def.input_fields.iter() {
+            match new_def.input_fields.get(field) {
+                None => {
+                    changes.insert(Change::InputFieldRemoved(name.clone(), field.clone()));
+                }
+                Some(new_field)
+                    if !is_safe_input_change(&old_field.value_type, &new_field.value_type) =>
+                {
+                    changes.insert(Change::InputFieldTypeChanged(
+                        name.clone(),
+                        field.clone(),
+                        old_field.value_type.to_string(),
+                        new_field.value_type.to_string(),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (field, new_field) in new_def.input_fields.iter() {
+            if new_field.required && !old_def.input_fields.contains_key(field) {
+                changes.insert(Change::RequiredInputFieldAdded(name.clone(), field.clone()));
+            }
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use graphql_parser::{parse_query, parse_schema};
+
+    use super::*;
+
+    const OLD: &str = r#"
+        type Query {
+          publications(first: Int, order: Order): [Publication!]!
+        }
+
+        type Publication {
+          id: ID!
+          rent: Float
+          city: String
+        }
+
+        enum Order {
+          RENT_ASC
+          RENT_DESC
+        }
+    "#;
+
+    const NEW: &str = r#"
+        type Query {
+          publications(first: Int!, order: Order, locale: String!): [Publication!]!
+        }
+
+        type Publication {
+          id: String!
+          rent: Float!
+          country: String
+        }
+
+        enum Order {
+          RENT_ASC
+        }
+    "#;
+
+    const QUERIES: &str = r#"
+        query GetPublications($order: Order) {
+          publications(first: 10, order: $order) {
+            ...Details
+          }
+        }
+
+        fragment Details on Publication {
+          id
+          rent
+          city
+        }
+    "#;
+
+    #[test]
+    fn test_breaking_changes() {
+        let old = Schema::from(&parse_schema::<String>(OLD).unwrap());
+        let new = Schema::from(&parse_schema::<String>(NEW).unwrap());
+
+        let usage = Usage::of(&old, &parse_query::<String>(QUERIES).unwrap());
+
+        let changes: Vec<String> = breaking_changes(&old, &new, &usage)
+            .iter()
+            .map(Change::to_string)
+            .collect();
+
+        assert_eq!(
+            changes,
+            [
+                "Field Publication.city was removed",
+                "Field Publication.id changed type from ID! to String!",
+                "Argument first of Query.publications changed type from Int to Int!",
+                "Required argument locale was added to Query.publications",
+                "Value RENT_DESC of enum Order was removed",
+            ]
+        );
+
+        assert!(breaking_changes(&old, &old, &usage).is_empty());
+    }
+}
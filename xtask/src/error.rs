@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+
+    #[error("Failed to write {0}: {1}")]
+    Write(PathBuf, std::io::Error),
+
+    #[error("Failed to parse the schema: {0}")]
+    ParseSchema(#[from] graphql_parser::schema::ParseError),
+
+    #[error("Failed to parse the queries: {0}")]
+    ParseQuery(#[from] graphql_parser::query::ParseError),
+
+    #[error("Failed to parse the introspection result: {0}")]
+    ParseIntrospection(#[from] serde_json::Error),
+
+    #[error("Invalid introspection result: {0}")]
+    Introspection(String),
+
+    #[error("Failed to fetch the introspection result: {0}")]
+    HttpRequest(#[from] reqwest::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
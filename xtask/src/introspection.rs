@@ -0,0 +1,415 @@
+//! Converting the result of an introspection query into a schema document, so it can be printed as SDL.
+
+use graphql_parser::{
+    query::{self, OperationDefinition},
+    schema::{
+        Definition, Directive, Document, EnumType, EnumValue, Field, InputObjectType, InputValue,
+        InterfaceType, ObjectType, ScalarType, SchemaDefinition, Type, TypeDefinition, UnionType,
+        Value,
+    },
+    Pos,
+};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// The introspection query, selecting everything needed to reconstruct the schema.
+pub const QUERY: &str = include_str!("introspection.graphql");
+
+/// Scalars every GraphQL schema has, which are not declared in SDL.
+const BUILT_IN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Response {
+    Full { data: Data },
+    Data(Data),
+}
+
+#[derive(Deserialize, Debug)]
+struct Data {
+    #[serde(rename = "__schema")]
+    schema: Schema,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Schema {
+    query_type: Option<Named>,
+    mutation_type: Option<Named>,
+    subscription_type: Option<Named>,
+    types: Vec<FullType>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Named {
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FullType {
+    kind: String,
+    name: String,
+    description: Option<String>,
+    fields: Option<Vec<FullField>>,
+    input_fields: Option<Vec<FullInputValue>>,
+    interfaces: Option<Vec<TypeRef>>,
+    enum_values: Option<Vec<FullEnumValue>>,
+    possible_types: Option<Vec<TypeRef>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FullField {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    args: Vec<FullInputValue>,
+    #[serde(rename = "type")]
+    type_ref: TypeRef,
+    #[serde(default)]
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FullInputValue {
+    name: String,
+    description: Option<String>,
+    #[serde(rename = "type")]
+    type_ref: TypeRef,
+    default_value: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct FullEnumValue {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TypeRef {
+    kind: String,
+    name: Option<String>,
+    of_type: Option<Box<TypeRef>>,
+}
+
+/// Convert an introspection result, either the whole response or only its data, into a schema document.
+pub fn to_schema(introspection: &str) -> Result<Document<'static, String>> {
+    let schema = match serde_json::from_str(introspection)? {
+        Response::Full { data } | Response::Data(data) => data.schema,
+    };
+
+    let mut definitions = Vec::new();
+
+    let root = |named: &Option<Named>, default: &str| {
+        named
+            .as_ref()
+            .filter(|named| named.name != default)
+            .map(|named| named.name.clone())
+    };
+
+    let query = root(&schema.query_type, "Query");
+    let mutation = root(&schema.mutation_type, "Mutation");
+    let subscription = root(&schema.subscription_type, "Subscription");
+
+    // The schema definition can be left out when the root types have their default names.
+    if query.is_some() || mutation.is_some() || subscription.is_some() {
+        definitions.push(Definition::SchemaDefinition(SchemaDefinition {
+            position: Pos::default(),
+            directives: Vec::new(),
+            query: schema.query_type.map(|named| named.name),
+            mutation: schema.mutation_type.map(|named| named.name),
+            subscription: schema.subscription_type.map(|named| named.name),
+        }));
+    }
+
+    let mut types: Vec<&FullType> = schema
+        .types
+        .iter()
+        .filter(|full_type| {
+            !full_type.name.starts_with("__")
+                && !BUILT_IN_SCALARS.contains(&full_type.name.as_str())
+        })
+        .collect();
+
+    types.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for full_type in types {
+        definitions.push(Definition::TypeDefinition(convert_type_definition(
+            full_type,
+        )?));
+    }
+
+    Ok(Document { definitions })
+}
+
+fn convert_type_definition(full_type: &FullType) -> Result<TypeDefinition<'static, String>> {
+    let name = full_type.name.clone();
+    let description = full_type.description.clone();
+
+    let type_definition = match full_type.kind.as_str() {
+        "SCALAR" => TypeDefinition::Scalar(ScalarType {
+            description,
+            ..ScalarType::new(name)
+        }),
+        "OBJECT" => TypeDefinition::Object(ObjectType {
+            description,
+            implements_interfaces: names(&full_type.interfaces)?,
+            fields: convert_fields(&full_type.fields)?,
+            ..ObjectType::new(name)
+        }),
+        "INTERFACE" => TypeDefinition::Interface(InterfaceType {
+            description,
+            implements_interfaces: names(&full_type.interfaces)?,
+            fields: convert_fields(&full_type.fields)?,
+            ..InterfaceType::new(name)
+        }),
+        "UNION" => TypeDefinition::Union(UnionType {
+            description,
+            types: names(&full_type.possible_types)?,
+            ..UnionType::new(name)
+        }),
+        "ENUM" => TypeDefinition::Enum(EnumType {
+            description,
+            values: full_type
+                .enum_values
+                .iter()
+                .flatten()
+                .map(|value| EnumValue {
+                    position: Pos::default(),
+                    description: value.description.clone(),
+                    name: value.name.clone(),
+                    directives: deprecated(value.is_deprecated, &value.deprecation_reason),
+                })
+                .collect(),
+            ..EnumType::new(name)
+        }),
+        "INPUT_OBJECT" => TypeDefinition::InputObject(InputObjectType {
+            description,
+            fields: convert_input_values(full_type.input_fields.iter().flatten())?,
+            ..InputObjectType::new(name)
+        }),
+        kind => {
+            return Err(Error::Introspection(format!(
+                "type {} has unknown kind {}",
+                full_type.name, kind
+            )))
+        }
+    };
+
+    Ok(type_definition)
+}
+
+fn convert_fields(fields: &Option<Vec<FullField>>) -> Result<Vec<Field<'static, String>>> {
+    fields
+        .iter()
+        .flatten()
+        .map(|field| {
+            Ok(Field {
+                position: Pos::default(),
+                description: field.description.clone(),
+                name: field.name.clone(),
+                arguments: convert_input_values(field.args.iter())?,
+                field_type: convert_type(&field.type_ref)?,
+                directives: deprecated(field.is_deprecated, &field.deprecation_reason),
+            })
+        })
+        .collect()
+}
+
+fn convert_input_values<'a, I: Iterator<Item = &'a FullInputValue>>(
+    input_values: I,
+) -> Result<Vec<InputValue<'static, String>>> {
+    input_values
+        .map(|input_value| {
+            Ok(InputValue {
+                position: Pos::default(),
+                description: input_value.description.clone(),
+                name: input_value.name.clone(),
+                value_type: convert_type(&input_value.type_ref)?,
+                default_value: input_value
+                    .default_value
+                    .as_deref()
+                    .map(parse_value)
+                    .transpose()?,
+                directives: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn convert_type(type_ref: &TypeRef) -> Result<Type<'static, String>> {
+    let of_type = || {
+        type_ref.of_type.as_deref().ok_or_else(|| {
+            Error::Introspection(format!("{} type without an inner type", type_ref.kind))
+        })
+    };
+
+    match type_ref.kind.as_str() {
+        "NON_NULL" => Ok(Type::NonNullType(Box::new(convert_type(of_type()?)?))),
+        "LIST" => Ok(Type::ListType(Box::new(convert_type(of_type()?)?))),
+        _ => {
+            type_ref.name.clone().map(Type::NamedType).ok_or_else(|| {
+                Error::Introspection(format!("{} type without a name", type_ref.kind))
+            })
+        }
+    }
+}
+
+fn names(type_refs: &Option<Vec<TypeRef>>) -> Result<Vec<String>> {
+    type_refs
+        .iter()
+        .flatten()
+        .map(|type_ref| {
+            type_ref.name.clone().ok_or_else(|| {
+                Error::Introspection(format!("{} type without a name", type_ref.kind))
+            })
+        })
+        .collect()
+}
+
+fn deprecated(is_deprecated: bool, reason: &Option<String>) -> Vec<Directive<'static, String>> {
+    if !is_deprecated {
+        return Vec::new();
+    }
+
+    let arguments = reason
+        .iter()
+        .map(|reason| (String::from("reason"), Value::String(reason.clone())))
+        .collect();
+
+    vec![Directive {
+        position: Pos::default(),
+        name: String::from("deprecated"),
+        arguments,
+    }]
+}
+
+/// Default values are given as GraphQL literals, parse one as the default of a variable.
+fn parse_value(literal: &str) -> Result<Value<'static, String>> {
+    let source = format!("query($value: Boolean = {}) {{ __typename }}", literal);
+
+    let document = query::parse_query::<String>(&source)?;
+
+    let default_value = document
+        .definitions
+        .first()
+        .and_then(|definition| match definition {
+            query::Definition::Operation(OperationDefinition::Query(query)) => query
+                .variable_definitions
+                .first()
+                .and_then(|variable| variable.default_value.as_ref()),
+            _ => None,
+        });
+
+    default_value
+        .map(Value::into_static)
+        .ok_or_else(|| Error::Introspection(format!("invalid default value {}", literal)))
+}
+
+#[cfg(test)]
+mod tests {
+    use graphql_parser::parse_schema;
+    use serde_json::json;
+
+    use super::*;
+    use crate::diff;
+
+    #[test]
+    fn test_to_schema() {
+        let named = |kind: &str, name: &str| json!({ "kind": kind, "name": name, "ofType": null });
+        let non_null = |of_type| json!({ "kind": "NON_NULL", "name": null, "ofType": of_type });
+
+        let introspection = json!({
+            "data": {
+                "__schema": {
+                    "queryType": { "name": "Query" },
+                    "mutationType": null,
+                    "subscriptionType": null,
+                    "types": [
+                        {
+                            "kind": "OBJECT",
+                            "name": "Query",
+                            "fields": [{
+                                "name": "publications",
+                                "args": [{
+                                    "name": "first",
+                                    "type": named("SCALAR", "Int"),
+                                    "defaultValue": "20",
+                                }],
+                                "type": non_null(json!({
+                                    "kind": "LIST",
+                                    "name": null,
+                                    "ofType": non_null(named("OBJECT", "Publication")),
+                                })),
+                                "isDeprecated": false,
+                            }],
+                            "interfaces": [],
+                        },
+                        {
+                            "kind": "OBJECT",
+                            "name": "Publication",
+                            "fields": [
+                                { "name": "id", "args": [], "type": non_null(named("SCALAR", "ID")) },
+                                {
+                                    "name": "order",
+                                    "args": [],
+                                    "type": named("ENUM", "Order"),
+                                    "isDeprecated": true,
+                                    "deprecationReason": "Use sorting instead",
+                                },
+                            ],
+                            "interfaces": [],
+                        },
+                        {
+                            "kind": "ENUM",
+                            "name": "Order",
+                            "enumValues": [{ "name": "RENT_ASC" }, { "name": "RENT_DESC" }],
+                        },
+                        { "kind": "SCALAR", "name": "ID" },
+                        { "kind": "OBJECT", "name": "__Type", "fields": [] },
+                    ],
+                },
+            },
+        });
+
+        let document = to_schema(&introspection.to_string()).unwrap();
+        let sdl = document.to_string();
+
+        assert!(sdl.contains("publications(first: Int = 20): [Publication!]!"));
+        assert!(sdl.contains("@deprecated(reason: \"Use sorting instead\")"));
+        assert!(!sdl.contains("scalar ID") && !sdl.contains("__Type"));
+
+        let expected = parse_schema::<String>(
+            r#"
+            enum Order {
+              RENT_ASC
+              RENT_DESC
+            }
+
+            type Publication {
+              id: ID!
+              order: Order
+            }
+
+            type Query {
+              publications(first: Int = 20): [Publication!]!
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            diff::Schema::from(&parse_schema::<String>(&sdl).unwrap()),
+            diff::Schema::from(&expected)
+        );
+    }
+}
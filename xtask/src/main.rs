@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use graphql_parser::{parse_query, parse_schema};
+use serde_json::json;
+
+mod diff;
+mod error;
+mod introspection;
+
+use diff::{Schema, Usage};
+use error::{Error, Result};
+
+/// The SSHN GraphQL gateway, which the bundled schema is a snapshot of.
+const GRAPHQL_URL: &str = "https://gateway.embracecloud.nl/graphql";
+
+/// Development tasks for the SSHN workspace.
+#[derive(Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Report changes of the live schema that break the operations in queries.graphql.
+    /// Exits with 1 if there are any.
+    SchemaDiff {
+        /// Read a saved introspection result instead of fetching it, to run offline.
+        #[arg(short, long)]
+        introspection: Option<PathBuf>,
+
+        /// The GraphQL endpoint to fetch the introspection result from.
+        #[arg(long, default_value = GRAPHQL_URL, conflicts_with = "introspection")]
+        url: String,
+
+        /// Save the fetched introspection result to this file.
+        #[arg(long, conflicts_with = "introspection")]
+        save: Option<PathBuf>,
+
+        /// Write the introspected schema as SDL to this file, e.g. to update the snapshot.
+        #[arg(long)]
+        write_sdl: Option<PathBuf>,
+
+        /// The schema snapshot the operations are generated from [default: sshn-lib/schema.graphql]
+        #[arg(long)]
+        schema: Option<PathBuf>,
+
+        /// The operations to check [default: sshn-lib/queries.graphql]
+        #[arg(long)]
+        queries: Option<PathBuf>,
+    },
+}
+
+/// Resolve a path relative to the root of the workspace, so tasks can be run from any directory.
+fn workspace_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(path)
+}
+
+fn read<P: AsRef<Path>>(path: P) -> Result<String> {
+    fs::read_to_string(path.as_ref()).map_err(|error| Error::Read(path.as_ref().into(), error))
+}
+
+fn write<P: AsRef<Path>>(path: P, contents: &str) -> Result<()> {
+    fs::write(path.as_ref(), contents).map_err(|error| Error::Write(path.as_ref().into(), error))
+}
+
+async fn fetch_introspection(url: &str) -> Result<String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&json!({
+            "operationName": "IntrospectionQuery",
+            "query": introspection::QUERY,
+        }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(response.text().await?)
+}
+
+/// Compare the introspection result with the snapshot, returning whether the operations still work.
+async fn schema_diff(
+    introspection: Option<PathBuf>,
+    url: String,
+    save: Option<PathBuf>,
+    write_sdl: Option<PathBuf>,
+    schema: Option<PathBuf>,
+    queries: Option<PathBuf>,
+) -> Result<bool> {
+    let introspection = match introspection {
+        Some(path) => read(path)?,
+        None => {
+            eprintln!("Fetching the schema from {}", url);
+
+            let introspection = fetch_introspection(&url).await?;
+
+            if let Some(path) = save {
+                write(path, &introspection)?;
+            }
+
+            introspection
+        }
+    };
+
+    let live = introspection::to_schema(&introspection)?;
+
+    if let Some(path) = write_sdl {
+        write(path, &live.to_string())?;
+    }
+
+    let snapshot = read(schema.unwrap_or_else(|| workspace_path("sshn-lib/schema.graphql")))?;
+    let snapshot = parse_schema::<String>(&snapshot)?;
+
+    let queries = read(queries.unwrap_or_else(|| workspace_path("sshn-lib/queries.graphql")))?;
+    let queries = parse_query::<String>(&queries)?;
+
+    let old = Schema::from(&snapshot);
+    let new = Schema::from(&live);
+
+    let changes = diff::breaking_changes(&old, &new, &Usage::of(&old, &queries));
+
+    if changes.is_empty() {
+        println!("No changes break the operations in queries.graphql");
+
+        return Ok(true);
+    }
+
+    println!(
+        "{} changes break the operations in queries.graphql:",
+        changes.len()
+    );
+
+    for change in changes {
+        println!("  {}", change);
+    }
+
+    Ok(false)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let result = match args.command {
+        Commands::SchemaDiff {
+            introspection,
+            url,
+            save,
+            write_sdl,
+            schema,
+            queries,
+        } => schema_diff(introspection, url, save, write_sdl, schema, queries).await,
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("{}", error);
+
+            ExitCode::from(2)
+        }
+    }
+}